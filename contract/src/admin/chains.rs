@@ -0,0 +1,221 @@
+// admin/chains.rs
+use crate::*;
+use near_sdk::json_types::U128;
+
+/// Chains every fresh deployment starts with. The admin can change or remove them later.
+pub fn default_chain_registry() -> Vec<(ChainId, ChainMetadata)> {
+    let near = |network, display_name: &str| {
+        (
+            ChainId::Near { network },
+            ChainMetadata {
+                display_name: display_name.to_string(),
+                native_decimals: 24,
+                default_gas_limit: None,
+                max_gas_limit: Some(U64(Gas::from_tgas(300).as_gas())),
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                supports_eip1559: false,
+                enabled: true,
+            },
+        )
+    };
+    let bitcoin = |network, display_name: &str| {
+        (
+            ChainId::Bitcoin { network },
+            ChainMetadata {
+                display_name: display_name.to_string(),
+                native_decimals: 8,
+                default_gas_limit: None,
+                max_gas_limit: None,
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                supports_eip1559: false,
+                enabled: true,
+            },
+        )
+    };
+    let evm = |chain_id, display_name: &str| {
+        (
+            ChainId::Evm(chain_id),
+            ChainMetadata {
+                display_name: display_name.to_string(),
+                native_decimals: 18,
+                default_gas_limit: Some(U64(100_000)),
+                max_gas_limit: Some(U64(30_000_000)),
+                max_fee_per_gas: None,
+                max_priority_fee_per_gas: None,
+                supports_eip1559: true,
+                enabled: true,
+            },
+        )
+    };
+
+    vec![
+        near(NearNetwork::Mainnet, "NEAR"),
+        near(NearNetwork::Testnet, "NEAR Testnet"),
+        bitcoin(BitcoinNetwork::Mainnet, "Bitcoin"),
+        bitcoin(BitcoinNetwork::Testnet, "Bitcoin Testnet"),
+        evm(1313161555, "Aurora Testnet"),
+        evm(84532, "Base Sepolia"),
+    ]
+}
+
+#[near]
+impl Contract {
+    /// Adds a chain to the registry or replaces its metadata.
+    /// Storage is paid by the attached deposit.
+    #[payable]
+    pub fn set_chain_metadata(&mut self, chain_id: ChainId, metadata: ChainMetadata) {
+        self.assert_admin();
+
+        let initial_storage = env::storage_usage();
+        self.chain_registry.insert(chain_id.clone(), metadata.clone());
+        self.chain_registry.flush();
+        self.adjust_deposit(initial_storage, env::storage_usage());
+
+        TrialEvent::ChainUpdated(vec![ChainUpdatedData { chain_id, metadata }]).emit();
+    }
+
+    /// Enables or disables a registered chain without touching the rest of its metadata.
+    pub fn set_chain_enabled(&mut self, chain_id: ChainId, enabled: bool) {
+        self.assert_admin();
+
+        let metadata = self.chain_registry.get_mut(&chain_id).unwrap_or_else(|| {
+            TrialError::UnknownChain {
+                chain_id: chain_id.clone(),
+            }
+            .panic()
+        });
+        metadata.enabled = enabled;
+        let metadata = metadata.clone();
+        self.chain_registry.flush();
+
+        TrialEvent::ChainUpdated(vec![ChainUpdatedData { chain_id, metadata }]).emit();
+    }
+
+    /// Removes a chain from the registry and refunds the freed storage.
+    /// Neither new nor existing trials can use the chain afterwards.
+    pub fn remove_chain(&mut self, chain_id: ChainId) {
+        self.assert_admin();
+
+        let initial_storage = env::storage_usage();
+        if self.chain_registry.remove(&chain_id).is_none() {
+            TrialError::UnknownChain { chain_id }.panic();
+        }
+        self.chain_registry.flush();
+        self.adjust_deposit(initial_storage, env::storage_usage());
+
+        TrialEvent::ChainRemoved(vec![ChainRemovedData { chain_id }]).emit();
+    }
+
+    /// Adds the default chains to the registry, keeping any entry that already exists.
+    pub(crate) fn internal_seed_chain_registry(&mut self) {
+        for (chain_id, metadata) in default_chain_registry() {
+            if !self.chain_registry.contains_key(&chain_id) {
+                self.chain_registry.insert(chain_id, metadata);
+            }
+        }
+        self.chain_registry.flush();
+    }
+
+    /// Panics if the chain isn't registered, otherwise returns its metadata.
+    pub(crate) fn internal_get_chain_metadata(&self, chain_id: &ChainId) -> &ChainMetadata {
+        self.chain_registry.get(chain_id).unwrap_or_else(|| {
+            TrialError::UnknownChain {
+                chain_id: chain_id.clone(),
+            }
+            .panic()
+        })
+    }
+
+    /// Panics unless the chain is registered and enabled, otherwise returns its metadata.
+    pub(crate) fn assert_chain_enabled(&self, chain_id: &ChainId) -> ChainMetadata {
+        let metadata = self.internal_get_chain_metadata(chain_id);
+        if !metadata.enabled {
+            TrialError::ChainDisabled {
+                chain_id: chain_id.clone(),
+            }
+            .panic();
+        }
+        metadata.clone()
+    }
+
+    /// Falls back to the chain's default gas limit when none is given.
+    /// Panics if the chain isn't registered.
+    pub(crate) fn internal_gas_limit(&self, chain_id: &ChainId, gas_limit: Option<U128>) -> U128 {
+        let metadata = self.internal_get_chain_metadata(chain_id);
+        gas_limit
+            .or_else(|| {
+                metadata
+                    .default_gas_limit
+                    .map(|default_gas_limit| U128(default_gas_limit.0 as u128))
+            })
+            .unwrap_or_else(|| TrialError::MissingGasLimit.panic())
+    }
+
+    /// Falls back to EIP-1559 transactions on chains that support them, and legacy otherwise.
+    /// Panics if the chain isn't registered.
+    pub(crate) fn internal_evm_transaction_type(
+        &self,
+        chain_id: &ChainId,
+        transaction_type: Option<EvmTransactionType>,
+    ) -> EvmTransactionType {
+        let metadata = self.internal_get_chain_metadata(chain_id);
+        transaction_type.unwrap_or(if metadata.supports_eip1559 {
+            EvmTransactionType::Eip1559
+        } else {
+            EvmTransactionType::Legacy
+        })
+    }
+
+    /// Panics if the fee parameters exceed the chain's caps, or if an EIP-1559
+    /// transaction is requested on a chain that doesn't support it.
+    /// Unregistered chains are rejected.
+    pub(crate) fn assert_evm_fees_allowed(
+        &self,
+        chain_id: &ChainId,
+        transaction_type: EvmTransactionType,
+        max_fee_per_gas: U128,
+        max_priority_fee_per_gas: Option<U128>,
+    ) {
+        let metadata = self.internal_get_chain_metadata(chain_id);
+        let violations = metadata.collect_evm_fee_violations(
+            chain_id,
+            Some(transaction_type),
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        );
+        if let Some(violation) = violations.first() {
+            violation.panic();
+        }
+    }
+
+    /// Checks an action against its chain's registry entry.
+    /// Chains removed from the registry after a trial was created can no longer be used.
+    pub(crate) fn collect_chain_violations(&self, action: &Action) -> Vec<TrialError> {
+        let chain_id = action.chain_id();
+        let mut violations = Vec::new();
+
+        match self.chain_registry.get(&chain_id) {
+            Some(metadata) => {
+                if !metadata.enabled {
+                    violations.push(TrialError::ChainDisabled { chain_id });
+                }
+                if !metadata.is_gas_within_limits(action.gas()) {
+                    violations.push(TrialError::GasExceedsLimit);
+                }
+                if let Action::EVM(evm_action) = action {
+                    violations.extend(metadata.collect_evm_fee_violations(
+                        &chain_id,
+                        evm_action.transaction_type,
+                        evm_action.max_fee_per_gas,
+                        evm_action.max_priority_fee_per_gas,
+                    ));
+                }
+            }
+            None => violations.push(TrialError::UnknownChain { chain_id }),
+        }
+
+        violations
+    }
+}
@@ -0,0 +1,125 @@
+// admin/controls.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Points the contract at a new MPC signer contract.
+    pub fn set_mpc_contract(&mut self, mpc_contract: AccountId) {
+        self.assert_admin();
+        self.mpc_contract = mpc_contract.clone();
+
+        TrialEvent::MpcContractUpdated(vec![MpcContractUpdatedData { mpc_contract }]).emit();
+    }
+
+    /// Proposes a new admin. The transfer completes once they call `accept_admin`.
+    pub fn propose_admin(&mut self, new_admin: AccountId) {
+        self.assert_admin();
+        self.pending_admin = Some(new_admin.clone());
+
+        TrialEvent::AdminProposed(vec![AdminData {
+            admin_account: new_admin,
+        }])
+        .emit();
+    }
+
+    /// Accepts a pending admin transfer. Only callable by the proposed admin.
+    pub fn accept_admin(&mut self) {
+        let caller = env::predecessor_account_id();
+        if self.pending_admin.as_ref() != Some(&caller) {
+            TrialError::NotPendingAdmin.panic();
+        }
+
+        self.admin_account = caller.clone();
+        self.pending_admin = None;
+
+        TrialEvent::AdminTransferred(vec![AdminData {
+            admin_account: caller,
+        }])
+        .emit();
+    }
+
+    /// Pauses signing, activation and key issuance across every trial.
+    pub fn pause(&mut self) {
+        self.assert_admin();
+        self.paused = true;
+
+        TrialEvent::ContractPaused(vec![AdminData {
+            admin_account: self.admin_account.clone(),
+        }])
+        .emit();
+    }
+
+    /// Lifts a global pause.
+    pub fn unpause(&mut self) {
+        self.assert_admin();
+        self.paused = false;
+
+        TrialEvent::ContractUnpaused(vec![AdminData {
+            admin_account: self.admin_account.clone(),
+        }])
+        .emit();
+    }
+
+    /// Removes keys whose trial no longer exists, along with their usage and access keys.
+    /// Keys issued before trials indexed their keys aren't removed by `delete_trial`,
+    /// so this is how they're cleaned up.
+    pub fn remove_orphaned_keys(&mut self, public_keys: Vec<PublicKey>) {
+        self.assert_admin();
+
+        let mut removed = Vec::new();
+        for public_key in public_keys {
+            let Some(key_usage) = self.internal_get_key_usage(&public_key) else {
+                continue;
+            };
+            if self.internal_get_trial(key_usage.trial_id).is_some() {
+                TrialError::KeyNotOrphaned {
+                    public_key: public_key_to_string(&public_key),
+                }
+                .panic();
+            }
+
+            self.internal_remove_key_usage(&public_key);
+            Promise::new(env::current_account_id()).delete_key(public_key.clone());
+            removed.push(KeysRemovedData {
+                trial_id: key_usage.trial_id,
+                public_keys: vec![public_key],
+                removed_by: env::predecessor_account_id(),
+            });
+        }
+        self.key_usage_by_pk.flush();
+
+        if !removed.is_empty() {
+            TrialEvent::KeysRemoved(removed).emit();
+        }
+    }
+
+    pub fn get_admin_account(&self) -> AccountId {
+        self.admin_account.clone()
+    }
+
+    pub fn get_pending_admin(&self) -> Option<AccountId> {
+        self.pending_admin.clone()
+    }
+
+    pub fn get_mpc_contract(&self) -> AccountId {
+        self.mpc_contract.clone()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Panics unless called by the admin account.
+    pub(crate) fn assert_admin(&self) {
+        if env::predecessor_account_id() != self.admin_account {
+            TrialError::NotAdmin.panic();
+        }
+    }
+
+    /// Panics while the contract is paused.
+    pub(crate) fn assert_not_paused(&self) {
+        if self.paused {
+            TrialError::ContractPaused.panic();
+        }
+    }
+}
@@ -0,0 +1,7 @@
+// admin/mod.rs
+//! Module for contract administration, restricted to the admin account.
+
+pub mod chains;
+pub mod controls;
+pub mod policy;
+pub mod upgrade;
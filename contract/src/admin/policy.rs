@@ -0,0 +1,126 @@
+// admin/policy.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Sets who may create trials and what they pay.
+    pub fn set_creation_policy(&mut self, policy: CreationPolicy) {
+        self.assert_admin();
+        self.creation_policy = policy.clone();
+
+        TrialEvent::CreationPolicyUpdated(vec![CreationPolicyData { policy }]).emit();
+    }
+
+    /// Adds accounts to the creator allowlist. Storage is paid by the attached deposit.
+    #[payable]
+    pub fn add_allowed_creators(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_admin();
+
+        let initial_storage = env::storage_usage();
+        for account_id in account_ids.iter() {
+            self.creator_allowlist.insert(account_id.clone());
+        }
+        self.creator_allowlist.flush();
+        self.adjust_deposit(initial_storage, env::storage_usage());
+
+        TrialEvent::CreatorsAllowed(vec![CreatorsData { account_ids }]).emit();
+    }
+
+    /// Removes accounts from the creator allowlist and refunds the freed storage.
+    pub fn remove_allowed_creators(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_admin();
+
+        let initial_storage = env::storage_usage();
+        for account_id in account_ids.iter() {
+            self.creator_allowlist.remove(account_id);
+        }
+        self.creator_allowlist.flush();
+        self.adjust_deposit(initial_storage, env::storage_usage());
+
+        TrialEvent::CreatorsDisallowed(vec![CreatorsData { account_ids }]).emit();
+    }
+
+    /// Withdraws collected platform fees to the admin. Withdraws everything if no amount is given.
+    pub fn withdraw_treasury(&mut self, amount: Option<NearToken>) -> Promise {
+        self.assert_admin();
+
+        let available = self.treasury_balance;
+        let amount = amount.unwrap_or(available);
+        self.treasury_balance = available.checked_sub(amount).unwrap_or_else(|| {
+            TrialError::InsufficientTreasuryBalance {
+                required: amount.as_yoctonear(),
+                available: available.as_yoctonear(),
+            }
+            .panic()
+        });
+
+        TrialEvent::TreasuryWithdrawn(vec![TreasuryWithdrawnData {
+            amount,
+            receiver_id: self.admin_account.clone(),
+        }])
+        .emit();
+
+        Promise::new(self.admin_account.clone()).transfer(amount)
+    }
+
+    pub fn get_creation_policy(&self) -> CreationPolicy {
+        self.creation_policy.clone()
+    }
+
+    pub fn is_allowed_creator(&self, account_id: AccountId) -> bool {
+        self.creator_allowlist.contains(&account_id)
+    }
+
+    pub fn get_treasury_balance(&self) -> NearToken {
+        self.treasury_balance
+    }
+
+    /// Panics if the account may not create trials, otherwise returns the fee for a new trial.
+    pub(crate) fn internal_trial_fee(&self, account_id: &AccountId) -> NearToken {
+        match &self.creation_policy {
+            CreationPolicy::Open => NearToken::from_yoctonear(0),
+            CreationPolicy::AllowlistOnly => {
+                if !self.creator_allowlist.contains(account_id) {
+                    TrialError::CreatorNotAllowed {
+                        account_id: account_id.clone(),
+                    }
+                    .panic();
+                }
+                NearToken::from_yoctonear(0)
+            }
+            CreationPolicy::OpenWithFee { trial_fee, .. } => *trial_fee,
+        }
+    }
+
+    /// Fee for issuing `num_keys` trial keys.
+    pub(crate) fn internal_key_fee(&self, num_keys: usize) -> NearToken {
+        match &self.creation_policy {
+            CreationPolicy::OpenWithFee { key_fee, .. } => key_fee
+                .checked_mul(num_keys as u128)
+                .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic()),
+            _ => NearToken::from_yoctonear(0),
+        }
+    }
+
+    /// Adds a collected fee to the treasury.
+    pub(crate) fn internal_collect_fee(&mut self, fee: NearToken) {
+        self.treasury_balance = self
+            .treasury_balance
+            .checked_add(fee)
+            .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
+    }
+
+    /// Collects a fee from the attached deposit and refunds whatever is left over.
+    pub(crate) fn internal_collect_attached_fee(&mut self, fee: NearToken) {
+        let refund = env::attached_deposit().checked_sub(fee).unwrap_or_else(|| {
+            TrialError::InsufficientDeposit {
+                required: fee.as_yoctonear(),
+            }
+            .panic()
+        });
+        if !refund.is_zero() {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+        self.internal_collect_fee(fee);
+    }
+}
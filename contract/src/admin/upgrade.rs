@@ -0,0 +1,287 @@
+// admin/upgrade.rs
+use crate::*;
+
+/// Gas reserved for the `migrate` call that follows a code upgrade
+const MIGRATE_GAS: Gas = Gas::from_tgas(100);
+
+/// `Contract` layout of the first deployed version, read back by `migrate`.
+#[near(serializers = [borsh])]
+pub struct OldContract {
+    pub trial_data_by_id: LookupMap<TrialId, TrialDataV1>,
+    pub key_usage_by_pk: LookupMap<PublicKey, KeyUsageV1>,
+    pub admin_account: AccountId,
+    pub mpc_contract: AccountId,
+    pub trial_nonce: TrialId,
+}
+
+#[near]
+impl Contract {
+    /// Deploys new contract code and migrates the state.
+    /// The wasm is passed as the raw call input rather than as JSON arguments.
+    pub fn upgrade(&mut self) -> Promise {
+        self.assert_admin();
+
+        let code = env::input().unwrap_or_else(|| TrialError::MissingContractCode.panic());
+        if code.is_empty() {
+            TrialError::MissingContractCode.panic();
+        }
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                "migrate".to_string(),
+                vec![],
+                NearToken::from_yoctonear(0),
+                MIGRATE_GAS,
+            )
+    }
+
+    /// Reloads the state after an upgrade.
+    /// Changes to the `Contract` layout itself are converted here, with new fields set to
+    /// their defaults and the chain registry seeded with the default chains.
+    /// Trials and keys move to versioned collections. Entries of the first version stay
+    /// under their old prefixes, are read from there as a fallback and are upgraded and
+    /// moved the next time they're modified. Their keys are indexed by trial at that point,
+    /// since keys can't be enumerated here.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old_state = env::state_read::<OldContract>()
+            .unwrap_or_else(|| TrialError::StateNotInitialized.panic());
+
+        let mut contract = Self {
+            trial_data_by_id: LookupMap::new(StorageKeys::VersionedTrialDataById),
+            key_usage_by_pk: LookupMap::new(StorageKeys::VersionedKeyUsageByPK),
+            trials_by_creator: LookupMap::new(StorageKeys::TrialsByCreator),
+            keys_by_trial: LookupMap::new(StorageKeys::KeysByTrial),
+            trial_stats_by_id: LookupMap::new(StorageKeys::TrialStatsById),
+            num_trials: 0,
+            admin_account: old_state.admin_account,
+            pending_admin: None,
+            mpc_contract: old_state.mpc_contract,
+            trial_nonce: old_state.trial_nonce,
+            paused: false,
+            creation_policy: CreationPolicy::Open,
+            creator_allowlist: LookupSet::new(StorageKeys::CreatorAllowlist),
+            treasury_balance: NearToken::from_yoctonear(0),
+            chain_registry: IterableMap::new(StorageKeys::ChainRegistry),
+        };
+        contract.internal_seed_chain_registry();
+        contract.internal_index_legacy_trials();
+        contract
+    }
+
+    /// Indexes the trials of the first version by creator and gives them empty stats.
+    /// Trial IDs were assigned sequentially, so every trial is found by walking the nonce.
+    pub(crate) fn internal_index_legacy_trials(&mut self) {
+        for trial_id in 1..=self.trial_nonce {
+            let Some(trial_data) =
+                read_legacy_entry::<_, TrialDataV1>(StorageKeys::TrialDataById, &trial_id)
+            else {
+                continue;
+            };
+
+            self.internal_add_trial_to_creator(&trial_data.creator_account_id, trial_id);
+            self.trial_stats_by_id
+                .insert(trial_id, TrialStats::default());
+            self.num_trials += 1;
+        }
+        self.trial_stats_by_id.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use omni_transaction::evm::types::Address;
+
+    // Layouts of the first deployed version, written out independently of `versioned.rs`
+    #[allow(dead_code)]
+    #[near(serializers = [borsh])]
+    struct BaselineContract {
+        trial_data_by_id: LookupMap<TrialId, BaselineTrialData>,
+        key_usage_by_pk: LookupMap<PublicKey, BaselineKeyUsage>,
+        admin_account: AccountId,
+        mpc_contract: AccountId,
+        trial_nonce: TrialId,
+    }
+
+    #[allow(dead_code)]
+    #[near(serializers = [borsh])]
+    struct BaselineTrialData {
+        constraints_by_chain_id: HashMap<String, BaselineChainConstraints>,
+        usage_constraints: Option<UsageConstraints>,
+        interaction_limits: Option<InteractionLimits>,
+        exit_conditions: Option<ExitConditions>,
+        expiration_time: Option<u64>,
+        creator_account_id: AccountId,
+    }
+
+    #[allow(dead_code)]
+    #[near(serializers = [borsh])]
+    enum BaselineChainConstraints {
+        NEAR(BaselineNearConstraints),
+        EVM(BaselineEvmConstraints),
+    }
+
+    #[allow(dead_code)]
+    #[near(serializers = [borsh])]
+    struct BaselineNearConstraints {
+        allowed_methods: Vec<String>,
+        allowed_contracts: Vec<AccountId>,
+        max_gas: Option<Gas>,
+        max_deposit: Option<NearToken>,
+        initial_deposit: NearToken,
+    }
+
+    #[allow(dead_code)]
+    #[near(serializers = [borsh])]
+    struct BaselineEvmConstraints {
+        allowed_methods: Vec<String>,
+        allowed_contracts: Vec<Address>,
+        max_gas: Option<u64>,
+        max_value: Option<U128>,
+        initial_deposit: String,
+    }
+
+    #[allow(dead_code)]
+    #[near(serializers = [borsh])]
+    struct BaselineKeyUsage {
+        trial_id: TrialId,
+        mpc_key: PublicKey,
+        account_id_by_chain_id: HashMap<String, BaselineUserAccountId>,
+        usage_stats: BaselineUsageStats,
+    }
+
+    #[allow(dead_code)]
+    #[near(serializers = [borsh])]
+    enum BaselineUserAccountId {
+        NEAR(AccountId),
+        EVM(Address),
+    }
+
+    #[allow(dead_code)]
+    #[near(serializers = [borsh])]
+    struct BaselineUsageStats {
+        total_interactions: u64,
+        interactions_per_day: HashMap<u64, u64>,
+        methods_called: HashMap<String, u64>,
+        contracts_called: HashMap<String, u64>,
+        gas_used: u128,
+        deposit_used: U128,
+    }
+
+    #[test]
+    fn migrate_reads_the_first_deployed_layout() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+
+        let creator = accounts(2);
+        let trial_account: AccountId = "trial.testnet".parse().unwrap();
+        let public_key: PublicKey = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+            .parse()
+            .unwrap();
+        let mpc_key: PublicKey = "ed25519:DcA2MzgpJbrUATQLLceocVckhhAqrkingax4oJ9kZ847"
+            .parse()
+            .unwrap();
+
+        // Trial 1 is still live, trial 2 was deleted before the upgrade
+        let mut baseline = BaselineContract {
+            trial_data_by_id: LookupMap::new(StorageKeys::TrialDataById),
+            key_usage_by_pk: LookupMap::new(StorageKeys::KeyUsageByPK),
+            admin_account: accounts(0),
+            mpc_contract: accounts(1),
+            trial_nonce: 2,
+        };
+        baseline.trial_data_by_id.insert(
+            1,
+            BaselineTrialData {
+                constraints_by_chain_id: HashMap::from([(
+                    "NEAR".to_string(),
+                    BaselineChainConstraints::NEAR(BaselineNearConstraints {
+                        allowed_methods: vec!["add_message".to_string()],
+                        allowed_contracts: vec!["guestbook.testnet".parse().unwrap()],
+                        max_gas: None,
+                        max_deposit: None,
+                        initial_deposit: NearToken::from_near(1),
+                    }),
+                )]),
+                usage_constraints: None,
+                interaction_limits: None,
+                exit_conditions: None,
+                expiration_time: None,
+                creator_account_id: creator.clone(),
+            },
+        );
+        baseline.key_usage_by_pk.insert(
+            public_key.clone(),
+            BaselineKeyUsage {
+                trial_id: 1,
+                mpc_key: mpc_key.clone(),
+                account_id_by_chain_id: HashMap::from([(
+                    "NEAR".to_string(),
+                    BaselineUserAccountId::NEAR(trial_account.clone()),
+                )]),
+                usage_stats: BaselineUsageStats {
+                    total_interactions: 3,
+                    interactions_per_day: HashMap::new(),
+                    methods_called: HashMap::from([("add_message".to_string(), 3)]),
+                    contracts_called: HashMap::new(),
+                    gas_used: 0,
+                    deposit_used: U128(0),
+                },
+            },
+        );
+        baseline.trial_data_by_id.flush();
+        baseline.key_usage_by_pk.flush();
+        env::state_write(&baseline);
+
+        let mut contract = Contract::migrate();
+        let near_testnet = ChainId::Near {
+            network: NearNetwork::Testnet,
+        };
+
+        assert_eq!(contract.admin_account, accounts(0));
+        assert_eq!(contract.mpc_contract, accounts(1));
+        assert_eq!(contract.trial_nonce, 2);
+        assert_eq!(contract.num_trials, 1);
+        assert!(contract
+            .trials_by_creator
+            .get(&creator)
+            .is_some_and(|trials| trials.contains(&1) && !trials.contains(&2)));
+        assert!(contract.trial_stats_by_id.contains_key(&1));
+        assert!(contract.chain_registry.contains_key(&near_testnet));
+
+        let trial_data = contract.internal_get_trial(1).unwrap();
+        assert_eq!(trial_data.creator_account_id, creator);
+        assert!(trial_data.balance.is_zero());
+        assert!(trial_data.is_method_allowed("add_message", &near_testnet));
+        assert!(!trial_data.is_method_allowed("add_message_2", &near_testnet));
+        assert!(trial_data.is_contract_allowed("guestbook.testnet", &near_testnet));
+        assert!(contract.internal_get_trial(2).is_none());
+
+        // Modifying a key moves it to the versioned collection and indexes it by trial
+        let key_usage = contract.internal_get_key_usage_mut(&public_key).unwrap();
+        assert_eq!(key_usage.trial_id, 1);
+        assert_eq!(key_usage.mpc_key, mpc_key);
+        assert_eq!(key_usage.usage_stats.total_interactions, 3);
+        assert_eq!(
+            key_usage.near_account(),
+            (NearNetwork::Testnet, trial_account)
+        );
+        contract.key_usage_by_pk.flush();
+
+        assert!(
+            read_legacy_entry::<_, KeyUsageV1>(StorageKeys::KeyUsageByPK, &public_key).is_none()
+        );
+        assert!(contract
+            .keys_by_trial
+            .get(&1)
+            .is_some_and(|keys| keys.contains(&public_key)));
+    }
+}
@@ -1,8 +1,8 @@
 // lib.rs
 use near_sdk::json_types::{Base58CryptoHash, U64};
-use near_sdk::store::LookupMap;
+use near_sdk::store::{IterableMap, IterableSet, LookupMap, LookupSet};
 use near_sdk::{
-    env, near, require, AccountId, Allowance, BorshStorageKey, Gas, GasWeight, NearToken,
+    env, near, AccountId, Allowance, BorshStorageKey, CryptoHash, Gas, GasWeight, NearToken,
     PanicOnDefault, Promise, PublicKey,
 };
 
@@ -17,12 +17,14 @@ use omni_transaction::{
 };
 use std::collections::HashMap;
 
+pub mod admin;
 pub mod models;
 pub mod trial_creator;
 pub mod trial_user;
 pub mod utils;
 pub mod views;
 
+pub use admin::*;
 pub use models::*;
 pub use trial_creator::*;
 pub use trial_user::*;
@@ -31,23 +33,45 @@ pub use utils::*;
 #[near(contract_state, serializers = [borsh])]
 #[derive(PanicOnDefault)]
 pub struct Contract {
-    pub trial_data_by_id: LookupMap<TrialId, TrialData>,
-    pub key_usage_by_pk: LookupMap<PublicKey, KeyUsage>,
+    pub trial_data_by_id: LookupMap<TrialId, VersionedTrialData>,
+    pub key_usage_by_pk: LookupMap<PublicKey, VersionedKeyUsage>,
+    pub trials_by_creator: LookupMap<AccountId, IterableSet<TrialId>>,
+    pub keys_by_trial: LookupMap<TrialId, IterableSet<PublicKey>>,
+    pub trial_stats_by_id: LookupMap<TrialId, TrialStats>,
+    pub num_trials: u64,
     pub admin_account: AccountId,
+    pub pending_admin: Option<AccountId>,
     pub mpc_contract: AccountId,
     pub trial_nonce: TrialId,
+    pub paused: bool,
+    pub creation_policy: CreationPolicy,
+    pub creator_allowlist: LookupSet<AccountId>,
+    pub treasury_balance: NearToken,
+    pub chain_registry: IterableMap<ChainId, ChainMetadata>,
 }
 
 #[near]
 impl Contract {
     #[init]
     pub fn new(admin_account: AccountId, mpc_contract: AccountId) -> Self {
-        Self {
-            trial_data_by_id: LookupMap::new(StorageKeys::TrialDataById),
-            key_usage_by_pk: LookupMap::new(StorageKeys::KeyUsageByPK),
+        let mut contract = Self {
+            trial_data_by_id: LookupMap::new(StorageKeys::VersionedTrialDataById),
+            key_usage_by_pk: LookupMap::new(StorageKeys::VersionedKeyUsageByPK),
+            trials_by_creator: LookupMap::new(StorageKeys::TrialsByCreator),
+            keys_by_trial: LookupMap::new(StorageKeys::KeysByTrial),
+            trial_stats_by_id: LookupMap::new(StorageKeys::TrialStatsById),
+            num_trials: 0,
             admin_account,
+            pending_admin: None,
             mpc_contract,
             trial_nonce: 0,
-        }
+            paused: false,
+            creation_policy: CreationPolicy::Open,
+            creator_allowlist: LookupSet::new(StorageKeys::CreatorAllowlist),
+            treasury_balance: NearToken::from_yoctonear(0),
+            chain_registry: IterableMap::new(StorageKeys::ChainRegistry),
+        };
+        contract.internal_seed_chain_registry();
+        contract
     }
 }
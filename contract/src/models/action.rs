@@ -8,11 +8,14 @@ use crate::*;
 pub enum Action {
     NEAR(NearAction),
     EVM(EvmAction),
+    Bitcoin(BitcoinAction),
+    SignMessage(SignMessageAction),
 }
 
 #[derive(Clone)]
 #[near(serializers = [json, borsh])]
 pub struct NearAction {
+    pub network: NearNetwork,
     pub method_name: String,
     pub contract_id: AccountId,
     pub gas_attached: Gas,
@@ -26,5 +29,142 @@ pub struct EvmAction {
     pub method_name: String,
     pub contract_address: Address,
     pub gas_limit: u128,
-    pub value: U128, // Value in wei
+    pub value: U128,           // Value in wei
+    pub max_fee_per_gas: U128, // Wei per gas, or the gas price of legacy transactions
+    /// Wei per gas. Legacy and EIP-2930 transactions have no priority fee.
+    pub max_priority_fee_per_gas: Option<U128>,
+    /// Envelope the transaction is signed in. Left out for user operations, whose envelope
+    /// the bundler picks, and for transactions using the chain's default.
+    pub transaction_type: Option<EvmTransactionType>,
+    /// Set when the call is made through an ERC-4337 smart account.
+    /// `contract_address` and `method_name` then describe the call the account executes.
+    pub user_operation: Option<UserOperationContext>,
+}
+
+/// How an ERC-4337 user operation is sent and paid for.
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub struct UserOperationContext {
+    pub entry_point: Address,
+    pub paymaster: Option<Address>, // None if the smart account pays for itself
+    pub overhead_gas: u128,         // verificationGasLimit + preVerificationGas
+}
+
+impl EvmAction {
+    /// Most the transaction can pay in fees, in wei.
+    /// For user operations this includes the verification and bundler overhead.
+    pub fn max_total_fee(&self) -> u128 {
+        let overhead_gas = self
+            .user_operation
+            .as_ref()
+            .map_or(0, |user_operation| user_operation.overhead_gas);
+        self.gas_limit
+            .saturating_add(overhead_gas)
+            .saturating_mul(self.max_fee_per_gas.0)
+    }
+
+    /// Most the account itself can pay in fees, in wei.
+    /// User operations sponsored by a paymaster cost the account nothing.
+    pub fn self_paid_fee(&self) -> u128 {
+        match &self.user_operation {
+            Some(UserOperationContext {
+                paymaster: Some(_), ..
+            }) => 0,
+            _ => self.max_total_fee(),
+        }
+    }
+}
+
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub struct BitcoinAction {
+    pub chain_id: ChainId,
+    pub recipient_scripts: Vec<String>, // Hex scriptPubKeys of every output that isn't change
+    pub amount: u64,                    // Satoshis sent to recipients
+    pub fee: u64,                       // Satoshis
+    pub vsize: u64,                     // Estimated virtual size in vbytes
+}
+
+/// An off-chain message signed for a recipient, such as a dapp login.
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub struct SignMessageAction {
+    pub chain_id: ChainId,
+    pub recipient: Option<String>,    // NEP-413 recipient or EIP-712 verifying contract
+    pub primary_type: Option<String>, // EIP-712 only
+}
+
+impl SignMessageAction {
+    /// Whether this is an EIP-191 message, which names neither a recipient nor a type.
+    pub fn is_personal_sign(&self) -> bool {
+        self.recipient.is_none() && self.primary_type.is_none()
+    }
+}
+
+impl BitcoinAction {
+    /// Fee rate in satoshis per virtual byte, rounded up.
+    pub fn fee_rate(&self) -> u64 {
+        self.fee.div_ceil(self.vsize.max(1))
+    }
+}
+
+impl Action {
+    /// The chain this action targets.
+    pub fn chain_id(&self) -> ChainId {
+        match self {
+            Action::NEAR(near_action) => ChainId::Near {
+                network: near_action.network,
+            },
+            Action::EVM(evm_action) => ChainId::Evm(evm_action.chain_id),
+            Action::Bitcoin(bitcoin_action) => bitcoin_action.chain_id.clone(),
+            Action::SignMessage(message_action) => message_action.chain_id.clone(),
+        }
+    }
+
+    /// The method being called.
+    pub fn method_name(&self) -> &str {
+        match self {
+            Action::NEAR(near_action) => &near_action.method_name,
+            Action::EVM(evm_action) => &evm_action.method_name,
+            Action::Bitcoin(_) => BITCOIN_TRANSFER_METHOD,
+            Action::SignMessage(_) => SIGN_MESSAGE_METHOD,
+        }
+    }
+
+    /// The contract being called, as an account ID or `0x` prefixed address.
+    /// For Bitcoin this is the first recipient's script, for messages the message recipient.
+    pub fn contract(&self) -> String {
+        match self {
+            Action::NEAR(near_action) => near_action.contract_id.to_string(),
+            Action::EVM(evm_action) => convert_address_to_hex_string(&evm_action.contract_address),
+            Action::Bitcoin(bitcoin_action) => bitcoin_action
+                .recipient_scripts
+                .first()
+                .cloned()
+                .unwrap_or_default(),
+            Action::SignMessage(message_action) => {
+                message_action.recipient.clone().unwrap_or_default()
+            }
+        }
+    }
+
+    /// Gas attached to the action (NEAR gas, EVM gas limit or Bitcoin virtual size).
+    pub fn gas(&self) -> u128 {
+        match self {
+            Action::NEAR(near_action) => near_action.gas_attached.as_gas() as u128,
+            Action::EVM(evm_action) => evm_action.gas_limit,
+            Action::Bitcoin(bitcoin_action) => bitcoin_action.vsize as u128,
+            Action::SignMessage(_) => 0,
+        }
+    }
+
+    /// Deposit attached to the action (yoctoNEAR, wei or satoshis).
+    pub fn deposit(&self) -> u128 {
+        match self {
+            Action::NEAR(near_action) => near_action.deposit_attached.as_yoctonear(),
+            Action::EVM(evm_action) => evm_action.value.0,
+            Action::Bitcoin(bitcoin_action) => bitcoin_action.amount as u128,
+            Action::SignMessage(_) => 0,
+        }
+    }
 }
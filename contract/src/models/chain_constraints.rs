@@ -9,26 +9,57 @@ use crate::*;
 pub enum ChainConstraints {
     NEAR(NearConstraints),
     EVM(EvmConstraints),
+    Bitcoin(BitcoinConstraints),
 }
 
 #[derive(Clone)]
 #[near(serializers = [json, borsh])]
 pub struct NearConstraints {
-    pub allowed_methods: Vec<String>,
-    pub allowed_contracts: Vec<AccountId>,
+    pub allowed_methods: Vec<MethodPattern>,
+    pub allowed_contracts: Vec<AccountPattern>,
     pub max_gas: Option<Gas>,
     pub max_deposit: Option<NearToken>,
     pub initial_deposit: NearToken,
+    /// NEP-413 recipients messages may be signed for, as account patterns.
+    /// Messages can't be signed if unset.
+    pub allowed_message_recipients: Option<Vec<AccountPattern>>,
 }
 
 #[derive(Clone)]
 #[near(serializers = [json, borsh])]
 pub struct EvmConstraints {
-    pub allowed_methods: Vec<String>,    // Function signatures or names
-    pub allowed_contracts: Vec<Address>, // Ethereum addresses
-    pub max_gas: Option<u64>,            // Gas limit
-    pub max_value: Option<U128>,         // Value in wei
-    pub initial_deposit: String,         // Value in wei
+    pub allowed_methods: Vec<MethodPattern>, // Function names or name patterns
+    pub allowed_contracts: Vec<Address>,     // Ethereum addresses
+    pub allowed_selectors: Vec<[u8; 4]>,     // Selectors user operations may execute
+    pub max_gas: Option<u64>,                // Gas limit
+    pub max_value: Option<U128>,             // Value in wei
+    pub initial_deposit: U128,               // Wei funding each account, and its fee budget
+    pub max_fee_per_gas_cap: Option<U128>,   // Wei per gas
+    pub max_priority_fee_cap: Option<U128>,  // Wei per gas
+    /// Whether EIP-191 `personal_sign` messages may be signed. Off by default.
+    #[serde(default)]
+    pub allow_personal_sign: bool,
+    /// EIP-712 verifying contracts typed data may be signed for, or `*` for any.
+    /// Typed data can't be signed if unset.
+    pub allowed_verifying_contracts: Option<Vec<AddressPattern>>,
+    /// EIP-712 primary types that may be signed, as method patterns.
+    /// Typed data can't be signed if unset.
+    pub allowed_primary_types: Option<Vec<MethodPattern>>,
+    /// ERC-4337 paymasters that may sponsor user operations, or `*` for any.
+    /// If set, user operations the smart account pays for itself are rejected.
+    pub allowed_paymasters: Option<Vec<AddressPattern>>,
+    /// ERC-4337 entry points user operations may be signed for, or `*` for any.
+    /// User operations are rejected if unset.
+    pub allowed_entry_points: Option<Vec<AddressPattern>>,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub struct BitcoinConstraints {
+    /// Hex encoded scriptPubKeys outputs may pay, or `*` for any.
+    pub allowed_recipient_scripts: Vec<ScriptPattern>,
+    pub max_amount: Option<U64>,   // Satoshis sent to recipients per transaction
+    pub max_fee_rate: Option<U64>, // Satoshis per virtual byte
 }
 
 #[derive(Clone)]
@@ -37,14 +68,36 @@ pub struct EvmConstraints {
 pub enum ExtChainConstraints {
     NEAR(NearConstraints),
     EVM(ExtEvmConstraints),
+    Bitcoin(BitcoinConstraints),
 }
 
 #[derive(Clone)]
 #[near(serializers = [json, borsh])]
 pub struct ExtEvmConstraints {
-    pub allowed_methods: Vec<String>,   // Function signatures or names
-    pub allowed_contracts: Vec<String>, // Ethereum addresses
-    pub max_gas: Option<u64>,           // Gas limit
-    pub max_value: Option<U128>,        // Value in wei
-    pub initial_deposit: String,        // Value in wei
+    pub allowed_methods: Vec<MethodPattern>, // Function names or name patterns
+    pub allowed_contracts: Vec<String>,      // Ethereum addresses
+    /// `0x` prefixed function selectors the calls user operations execute may use.
+    /// `allowed_methods` only applies to transactions, which name their method.
+    #[serde(default)]
+    pub allowed_selectors: Vec<String>,
+    pub max_gas: Option<u64>,                // Gas limit
+    pub max_value: Option<U128>,             // Value in wei
+    pub initial_deposit: String,             // Wei funding each account, and its fee budget
+    pub max_fee_per_gas_cap: Option<U128>,   // Wei per gas
+    pub max_priority_fee_cap: Option<U128>,  // Wei per gas
+    /// Whether EIP-191 `personal_sign` messages may be signed. Off by default.
+    #[serde(default)]
+    pub allow_personal_sign: bool,
+    /// EIP-712 verifying contracts typed data may be signed for, or `*` for any.
+    /// Typed data can't be signed if unset.
+    pub allowed_verifying_contracts: Option<Vec<AddressPattern>>,
+    /// EIP-712 primary types that may be signed, as method patterns.
+    /// Typed data can't be signed if unset.
+    pub allowed_primary_types: Option<Vec<MethodPattern>>,
+    /// ERC-4337 paymasters that may sponsor user operations, or `*` for any.
+    /// If set, user operations the smart account pays for itself are rejected.
+    pub allowed_paymasters: Option<Vec<AddressPattern>>,
+    /// ERC-4337 entry points user operations may be signed for, or `*` for any.
+    /// User operations are rejected if unset.
+    pub allowed_entry_points: Option<Vec<AddressPattern>>,
 }
@@ -1,58 +1,168 @@
 use crate::*;
-use std::cmp::Ordering;
-use std::cmp::{Ord, PartialOrd};
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::io;
+use std::str::FromStr;
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+/// A NEAR network.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 #[near(serializers = [json, borsh])]
-pub struct ChainId(pub String);
+#[serde(rename_all = "lowercase")]
+pub enum NearNetwork {
+    Mainnet,
+    Testnet,
+}
+
+/// A Bitcoin network.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "lowercase")]
+pub enum BitcoinNetwork {
+    Mainnet,
+    Testnet,
+}
+
+/// A chain a trial can be used on.
+///
+/// Exchanged in JSON and stored in borsh as its canonical string:
+/// - `NEAR:mainnet` / `NEAR:testnet`
+/// - the decimal EIP-155 chain ID for EVM chains, e.g. `1` or `84532`
+/// - `BITCOIN:mainnet` / `BITCOIN:testnet`
+///
+/// The legacy strings `NEAR` (testnet), `BITCOIN` and `BITCOIN_TESTNET` are still accepted.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum ChainId {
+    Near { network: NearNetwork },
+    Evm(u64),
+    Bitcoin { network: BitcoinNetwork },
+}
 
 impl ChainId {
     pub fn is_evm(&self) -> bool {
-        self.0 != "NEAR"
+        matches!(self, ChainId::Evm(_))
     }
 
     pub fn is_near(&self) -> bool {
-        self.0 == "NEAR"
+        matches!(self, ChainId::Near { .. })
+    }
+
+    pub fn is_bitcoin(&self) -> bool {
+        matches!(self, ChainId::Bitcoin { .. })
     }
 
     pub fn as_evm_chain_id(&self) -> Option<u64> {
-        if self.is_near() {
-            None
-        } else {
-            self.0.parse::<u64>().ok()
+        match self {
+            ChainId::Evm(chain_id) => Some(*chain_id),
+            _ => None,
+        }
+    }
+
+    /// Human-readable part of the chain's bech32 addresses, if it is a Bitcoin chain.
+    pub fn bitcoin_hrp(&self) -> Option<&'static str> {
+        match self {
+            ChainId::Bitcoin {
+                network: BitcoinNetwork::Mainnet,
+            } => Some("bc"),
+            ChainId::Bitcoin {
+                network: BitcoinNetwork::Testnet,
+            } => Some("tb"),
+            _ => None,
+        }
+    }
+
+    /// Account that creates top-level accounts on the chain, if it is a NEAR chain.
+    pub fn near_root_account(&self) -> Option<AccountId> {
+        match self {
+            ChainId::Near {
+                network: NearNetwork::Mainnet,
+            } => Some("near".parse().unwrap()),
+            ChainId::Near {
+                network: NearNetwork::Testnet,
+            } => Some("testnet".parse().unwrap()),
+            _ => None,
         }
     }
 }
 
 impl fmt::Display for ChainId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+        match self {
+            ChainId::Near {
+                network: NearNetwork::Mainnet,
+            } => write!(f, "NEAR:mainnet"),
+            ChainId::Near {
+                network: NearNetwork::Testnet,
+            } => write!(f, "NEAR:testnet"),
+            ChainId::Evm(chain_id) => write!(f, "{}", chain_id),
+            ChainId::Bitcoin {
+                network: BitcoinNetwork::Mainnet,
+            } => write!(f, "BITCOIN:mainnet"),
+            ChainId::Bitcoin {
+                network: BitcoinNetwork::Testnet,
+            } => write!(f, "BITCOIN:testnet"),
+        }
+    }
+}
+
+impl FromStr for ChainId {
+    type Err = TrialError;
+
+    fn from_str(chain_id: &str) -> Result<Self, Self::Err> {
+        match chain_id {
+            "NEAR:mainnet" => Ok(ChainId::Near {
+                network: NearNetwork::Mainnet,
+            }),
+            // Trials created before networks were tracked always meant testnet
+            "NEAR:testnet" | "NEAR" => Ok(ChainId::Near {
+                network: NearNetwork::Testnet,
+            }),
+            "BITCOIN:mainnet" | "BITCOIN" => Ok(ChainId::Bitcoin {
+                network: BitcoinNetwork::Mainnet,
+            }),
+            "BITCOIN:testnet" | "BITCOIN_TESTNET" => Ok(ChainId::Bitcoin {
+                network: BitcoinNetwork::Testnet,
+            }),
+            _ => chain_id
+                .parse()
+                .ok()
+                // Reject forms like `+1` or `01` so every chain has a single spelling
+                .filter(|evm_chain_id: &u64| evm_chain_id.to_string() == chain_id)
+                .map(ChainId::Evm)
+                .ok_or_else(|| TrialError::InvalidChainId {
+                    chain_id: chain_id.to_string(),
+                }),
+        }
     }
 }
 
-impl From<String> for ChainId {
-    fn from(s: String) -> Self {
-        ChainId(s)
+impl Serialize for ChainId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
     }
 }
 
-impl From<&str> for ChainId {
-    fn from(s: &str) -> Self {
-        ChainId(s.to_string())
+impl<'de> Deserialize<'de> for ChainId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let chain_id = String::deserialize(deserializer)?;
+        chain_id
+            .parse()
+            .map_err(|error: TrialError| de::Error::custom(error.message()))
     }
 }
 
-// Implementing PartialOrd manually
-impl PartialOrd for ChainId {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.0.partial_cmp(&other.0)
+// Stored as the canonical string so chain IDs written as plain strings keep loading
+impl BorshSerialize for ChainId {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.to_string(), writer)
     }
 }
 
-// Implementing Ord manually
-impl Ord for ChainId {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.0.cmp(&other.0)
+impl BorshDeserialize for ChainId {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let chain_id = String::deserialize_reader(reader)?;
+        chain_id.parse().map_err(|error: TrialError| {
+            io::Error::new(io::ErrorKind::InvalidData, error.message())
+        })
     }
 }
@@ -0,0 +1,65 @@
+// models/chain_metadata.rs
+use crate::*;
+use near_sdk::json_types::U128;
+
+/// Registry entry for a chain trials can be created on, managed by the admin.
+/// Fee caps only apply to EVM chains and are in wei.
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub struct ChainMetadata {
+    pub display_name: String,
+    pub native_decimals: u8,
+    pub default_gas_limit: Option<U64>, // Used when a caller leaves the gas limit out
+    pub max_gas_limit: Option<U64>,     // Upper bound for every trial on the chain
+    pub max_fee_per_gas: Option<U128>,
+    pub max_priority_fee_per_gas: Option<U128>,
+    pub supports_eip1559: bool,
+    pub enabled: bool, // Disabled chains can't be used by new or existing trials
+}
+
+impl ChainMetadata {
+    /// Whether `gas` is within the chain's maximum gas limit.
+    pub fn is_gas_within_limits(&self, gas: u128) -> bool {
+        match self.max_gas_limit {
+            Some(max_gas_limit) => gas <= max_gas_limit.0 as u128,
+            None => true,
+        }
+    }
+
+    /// Collects the ways EVM fee parameters break this entry: fees above its caps, or an
+    /// EIP-1559 transaction on a chain that doesn't support it. Transactions without a
+    /// priority fee are only checked against the max fee cap.
+    pub fn collect_evm_fee_violations(
+        &self,
+        chain_id: &ChainId,
+        transaction_type: Option<EvmTransactionType>,
+        max_fee_per_gas: U128,
+        max_priority_fee_per_gas: Option<U128>,
+    ) -> Vec<TrialError> {
+        let mut violations = Vec::new();
+
+        if transaction_type == Some(EvmTransactionType::Eip1559) && !self.supports_eip1559 {
+            violations.push(TrialError::Eip1559NotSupported {
+                chain_id: chain_id.clone(),
+            });
+        }
+        if let Some(cap) = self.max_fee_per_gas {
+            if max_fee_per_gas.0 > cap.0 {
+                violations.push(TrialError::FeeExceedsChainCap {
+                    fee: "max_fee_per_gas",
+                    cap: cap.0,
+                });
+            }
+        }
+        if let (Some(cap), Some(fee)) = (self.max_priority_fee_per_gas, max_priority_fee_per_gas) {
+            if fee.0 > cap.0 {
+                violations.push(TrialError::FeeExceedsChainCap {
+                    fee: "max_priority_fee_per_gas",
+                    cap: cap.0,
+                });
+            }
+        }
+
+        violations
+    }
+}
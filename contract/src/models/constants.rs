@@ -1,7 +1,45 @@
 // models/constants.rs
-/// Access key methods name for trial accounts
-pub const TRIAL_ACCESS_KEY_METHODS: &str =
-    "create_trial,activate_trial,delete_trial,add_trial_keys,call_evm_contract,call_near_contract,exit_trial";
+use near_sdk::{Gas, NearToken};
+
+/// Method name recorded for Bitcoin transfers, which have no contract method
+pub const BITCOIN_TRANSFER_METHOD: &str = "transfer";
+
+/// Method name recorded for off-chain message signatures
+pub const SIGN_MESSAGE_METHOD: &str = "sign_message";
+
+/// Most inputs a Bitcoin transfer can spend, since each input needs its own MPC signature
+pub const MAX_BITCOIN_INPUTS: usize = 5;
+
+/// Gas reserved for collecting the signatures of a multi-input Bitcoin transfer
+pub const MPC_SIGNATURES_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+/// Methods trial users call with their trial key.
+/// Trial access keys are restricted to exactly these, so creator and admin
+/// methods must never be added here.
+pub const TRIAL_USER_METHODS: &[&str] = &[
+    "activate_trial",
+    "call_near_contract",
+    "sign_near_delegate_action",
+    "sign_near_message",
+    "call_evm_contract",
+    "sign_evm_message",
+    "sign_evm_typed_data",
+    "sign_user_operation",
+    "call_bitcoin_transfer",
+    "exit_trial",
+];
+
+/// Prefix of NEP-366 delegate actions before hashing, `2^30 + 366` as defined by NEP-461
+pub const NEP_366_SIGNATURE_PREFIX: u32 = (1 << 30) + 366;
+
+/// Tag prefixed to NEP-413 messages before hashing, `2^31 + 413`
+pub const NEP_413_SIGN_MESSAGE_PREFIX: u32 = (1 << 31) + 413;
+
+/// Gas allowance given to a trial key when neither the key nor the trial sets one
+pub const DEFAULT_KEY_ALLOWANCE: NearToken = NearToken::from_millinear(250);
+
+/// Most keys `delete_trial` removes in one call, so large trials are deleted in batches
+pub const DELETE_TRIAL_KEYS_BATCH_SIZE: usize = 100;
 
 /// Length of an Ed25519 public key
 pub const ED25519_PUBLIC_KEY_LENGTH: usize = 32;
@@ -9,5 +47,17 @@ pub const ED25519_PUBLIC_KEY_LENGTH: usize = 32;
 /// Length of a SECP256K1 public key
 pub const SECP256K1_PUBLIC_KEY_LENGTH: usize = 64;
 
+/// Nanoseconds in a day, used to bucket usage by day
+pub const NANOSECONDS_PER_DAY: u64 = 86_400_000_000_000;
+
+/// Number of days of per-day usage buckets kept before older days are pruned
+pub const USAGE_STATS_DAY_WINDOW: u64 = 30;
+
+/// Number of payload hashes kept per key to reject signing the same payload twice
+pub const RECENT_PAYLOAD_HASHES_LIMIT: usize = 20;
+
+/// Number of methods returned in a trial's top methods
+pub const TOP_METHODS_LIMIT: usize = 10;
+
 /// Type alias for Trial IDs
 pub type TrialId = u32;
@@ -6,4 +6,15 @@ use crate::*;
 pub enum StorageKeys {
     TrialDataById,
     KeyUsageByPK,
+    TrialsByCreator,
+    TrialsByCreatorInner { account_id_hash: CryptoHash },
+    KeysByTrial,
+    KeysByTrialInner { trial_id: TrialId },
+    TrialStatsById,
+    CreatorAllowlist,
+    ChainRegistry,
+    // Versioned entries. `TrialDataById` and `KeyUsageByPK` still hold untagged entries
+    // written by the first version
+    VersionedTrialDataById,
+    VersionedKeyUsageByPK,
 }
@@ -0,0 +1,18 @@
+// models/creation_policy.rs
+use crate::*;
+
+/// Who may call `create_trial`, set by the admin.
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+pub enum CreationPolicy {
+    /// Anyone can create trials for free
+    Open,
+    /// Only accounts on the creator allowlist can create trials
+    AllowlistOnly,
+    /// Anyone can create trials, paying a flat fee per trial and per key into the treasury
+    OpenWithFee {
+        trial_fee: NearToken,
+        key_fee: NearToken,
+    },
+}
@@ -0,0 +1,334 @@
+// models/errors.rs
+use crate::*;
+use std::fmt;
+
+/// Errors surfaced by the contract.
+///
+/// Every error panics with a JSON object of the form
+/// `{"code":"METHOD_NOT_ALLOWED","message":"..."}`. The `code` is stable and
+/// meant to be matched on by clients, the `message` is for humans and may change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrialError {
+    NotAdmin,
+    NotPendingAdmin,
+    CreatorNotAllowed { account_id: AccountId },
+    ContractPaused,
+    MissingContractCode,
+    StateNotInitialized,
+    AccessDenied,
+    TrialNotFound { trial_id: TrialId },
+    NotTrialCreator,
+    SelfCallNotAllowed,
+    MissingTrialRole { role: TrialRole },
+    TrialExpired,
+    TrialPaused,
+    KeyFrozen,
+    KeyExited,
+    TransactionLimitReached,
+    ChainNotAllowed { chain_id: ChainId },
+    UnknownChain { chain_id: ChainId },
+    ChainDisabled { chain_id: ChainId },
+    MethodNotAllowed { method: String },
+    ContractNotAllowed { contract: String },
+    MessageRecipientNotAllowed { recipient: String },
+    PrimaryTypeNotAllowed { primary_type: String },
+    PersonalSignNotAllowed { chain_id: ChainId },
+    PaymasterNotAllowed { paymaster: String },
+    PaymasterRequired,
+    EntryPointNotAllowed { entry_point: String },
+    GasExceedsLimit,
+    DepositExceedsLimit,
+    FeeRateExceedsLimit,
+    EvmFeeExceedsLimit,
+    EvmFeeBudgetExceeded,
+    FeeExceedsChainCap { fee: &'static str, cap: u128 },
+    Eip1559NotSupported { chain_id: ChainId },
+    MissingGasLimit,
+    MissingEvmFee { field: &'static str },
+    InvalidChainId { chain_id: String },
+    ConstraintsMismatch { chain_id: String, expected: &'static str },
+    InvalidNearAccountId { account_id: String },
+    InvalidPublicKey { public_key: String },
+    InvalidEvmAddress { address: String },
+    InvalidBitcoinPublicKey { public_key: String },
+    InvalidBitcoinScript { script: String },
+    InvalidBitcoinTransaction { reason: &'static str },
+    BitcoinAddressMismatch { address: String, expected: String },
+    AlreadyActivated { chain_id: ChainId },
+    NotActivated { chain_id: String },
+    NonceTooLow { nonce: u64, last_nonce: u64 },
+    DuplicatePayload,
+    NoPayloadsToSign,
+    MpcSignatureFailed { index: u64 },
+    InvalidMessageNonce,
+    AmbiguousPersonalMessage,
+    InsufficientDeposit { required: u128 },
+    InvalidAllowance,
+    InvalidAmount { amount: String },
+    KeyAlreadyRegistered { public_key: String },
+    KeyNotOrphaned { public_key: String },
+    InsufficientTrialBalance { required: u128, available: u128 },
+    InsufficientTreasuryBalance { required: u128, available: u128 },
+    ArithmeticOverflow,
+    InvalidAbiValue { kind: &'static str, value: String },
+    InvalidTypedData { reason: String },
+    InvalidUserOperation { reason: &'static str },
+    AbiEncodingFailed,
+    SerializationFailed,
+}
+
+impl TrialError {
+    /// Stable, machine-readable error code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TrialError::NotAdmin => "NOT_ADMIN",
+            TrialError::NotPendingAdmin => "NOT_PENDING_ADMIN",
+            TrialError::CreatorNotAllowed { .. } => "CREATOR_NOT_ALLOWED",
+            TrialError::ContractPaused => "CONTRACT_PAUSED",
+            TrialError::MissingContractCode => "MISSING_CONTRACT_CODE",
+            TrialError::StateNotInitialized => "STATE_NOT_INITIALIZED",
+            TrialError::AccessDenied => "ACCESS_DENIED",
+            TrialError::TrialNotFound { .. } => "TRIAL_NOT_FOUND",
+            TrialError::NotTrialCreator => "NOT_TRIAL_CREATOR",
+            TrialError::SelfCallNotAllowed => "SELF_CALL_NOT_ALLOWED",
+            TrialError::MissingTrialRole { .. } => "MISSING_TRIAL_ROLE",
+            TrialError::TrialExpired => "TRIAL_EXPIRED",
+            TrialError::TrialPaused => "TRIAL_PAUSED",
+            TrialError::KeyFrozen => "KEY_FROZEN",
+            TrialError::KeyExited => "KEY_EXITED",
+            TrialError::TransactionLimitReached => "TRANSACTION_LIMIT_REACHED",
+            TrialError::ChainNotAllowed { .. } => "CHAIN_NOT_ALLOWED",
+            TrialError::UnknownChain { .. } => "UNKNOWN_CHAIN",
+            TrialError::ChainDisabled { .. } => "CHAIN_DISABLED",
+            TrialError::MethodNotAllowed { .. } => "METHOD_NOT_ALLOWED",
+            TrialError::ContractNotAllowed { .. } => "CONTRACT_NOT_ALLOWED",
+            TrialError::MessageRecipientNotAllowed { .. } => "MESSAGE_RECIPIENT_NOT_ALLOWED",
+            TrialError::PrimaryTypeNotAllowed { .. } => "PRIMARY_TYPE_NOT_ALLOWED",
+            TrialError::PersonalSignNotAllowed { .. } => "PERSONAL_SIGN_NOT_ALLOWED",
+            TrialError::PaymasterNotAllowed { .. } => "PAYMASTER_NOT_ALLOWED",
+            TrialError::PaymasterRequired => "PAYMASTER_REQUIRED",
+            TrialError::EntryPointNotAllowed { .. } => "ENTRY_POINT_NOT_ALLOWED",
+            TrialError::GasExceedsLimit => "GAS_EXCEEDS_LIMIT",
+            TrialError::DepositExceedsLimit => "DEPOSIT_EXCEEDS_LIMIT",
+            TrialError::FeeRateExceedsLimit => "FEE_RATE_EXCEEDS_LIMIT",
+            TrialError::EvmFeeExceedsLimit => "EVM_FEE_EXCEEDS_LIMIT",
+            TrialError::EvmFeeBudgetExceeded => "EVM_FEE_BUDGET_EXCEEDED",
+            TrialError::FeeExceedsChainCap { .. } => "FEE_EXCEEDS_CHAIN_CAP",
+            TrialError::Eip1559NotSupported { .. } => "EIP1559_NOT_SUPPORTED",
+            TrialError::MissingGasLimit => "MISSING_GAS_LIMIT",
+            TrialError::MissingEvmFee { .. } => "MISSING_EVM_FEE",
+            TrialError::InvalidChainId { .. } => "INVALID_CHAIN_ID",
+            TrialError::ConstraintsMismatch { .. } => "CONSTRAINTS_MISMATCH",
+            TrialError::InvalidNearAccountId { .. } => "INVALID_NEAR_ACCOUNT_ID",
+            TrialError::InvalidPublicKey { .. } => "INVALID_PUBLIC_KEY",
+            TrialError::InvalidEvmAddress { .. } => "INVALID_EVM_ADDRESS",
+            TrialError::InvalidBitcoinPublicKey { .. } => "INVALID_BITCOIN_PUBLIC_KEY",
+            TrialError::InvalidBitcoinScript { .. } => "INVALID_BITCOIN_SCRIPT",
+            TrialError::InvalidBitcoinTransaction { .. } => "INVALID_BITCOIN_TRANSACTION",
+            TrialError::BitcoinAddressMismatch { .. } => "BITCOIN_ADDRESS_MISMATCH",
+            TrialError::AlreadyActivated { .. } => "ALREADY_ACTIVATED",
+            TrialError::NotActivated { .. } => "NOT_ACTIVATED",
+            TrialError::NonceTooLow { .. } => "NONCE_TOO_LOW",
+            TrialError::DuplicatePayload => "DUPLICATE_PAYLOAD",
+            TrialError::NoPayloadsToSign => "NO_PAYLOADS_TO_SIGN",
+            TrialError::MpcSignatureFailed { .. } => "MPC_SIGNATURE_FAILED",
+            TrialError::InvalidMessageNonce => "INVALID_MESSAGE_NONCE",
+            TrialError::AmbiguousPersonalMessage => "AMBIGUOUS_PERSONAL_MESSAGE",
+            TrialError::InsufficientDeposit { .. } => "INSUFFICIENT_DEPOSIT",
+            TrialError::InvalidAllowance => "INVALID_ALLOWANCE",
+            TrialError::InvalidAmount { .. } => "INVALID_AMOUNT",
+            TrialError::KeyAlreadyRegistered { .. } => "KEY_ALREADY_REGISTERED",
+            TrialError::KeyNotOrphaned { .. } => "KEY_NOT_ORPHANED",
+            TrialError::InsufficientTrialBalance { .. } => "INSUFFICIENT_TRIAL_BALANCE",
+            TrialError::InsufficientTreasuryBalance { .. } => "INSUFFICIENT_TREASURY_BALANCE",
+            TrialError::ArithmeticOverflow => "ARITHMETIC_OVERFLOW",
+            TrialError::InvalidAbiValue { .. } => "INVALID_ABI_VALUE",
+            TrialError::InvalidTypedData { .. } => "INVALID_TYPED_DATA",
+            TrialError::InvalidUserOperation { .. } => "INVALID_USER_OPERATION",
+            TrialError::AbiEncodingFailed => "ABI_ENCODING_FAILED",
+            TrialError::SerializationFailed => "SERIALIZATION_FAILED",
+        }
+    }
+
+    /// Human-readable description of the error.
+    pub fn message(&self) -> String {
+        match self {
+            TrialError::NotAdmin => "Only the admin can do this".to_string(),
+            TrialError::NotPendingAdmin => "Only the proposed admin can accept".to_string(),
+            TrialError::CreatorNotAllowed { account_id } => {
+                format!("`{}` is not allowed to create trials", account_id)
+            }
+            TrialError::ContractPaused => "The contract is paused".to_string(),
+            TrialError::MissingContractCode => "No contract code was provided".to_string(),
+            TrialError::StateNotInitialized => "Contract state is not initialized".to_string(),
+            TrialError::AccessDenied => "Access denied".to_string(),
+            TrialError::TrialNotFound { trial_id } => {
+                format!("Trial {} does not exist", trial_id)
+            }
+            TrialError::NotTrialCreator => "Only the trial creator can do this".to_string(),
+            TrialError::SelfCallNotAllowed => {
+                "This method cannot be called by the contract itself".to_string()
+            }
+            TrialError::MissingTrialRole { role } => {
+                format!("Only the creator or a `{}` manager can do this", role)
+            }
+            TrialError::TrialExpired => "Trial period has expired".to_string(),
+            TrialError::TrialPaused => "The trial is paused by its creator".to_string(),
+            TrialError::KeyFrozen => "This key is frozen by the trial creator".to_string(),
+            TrialError::KeyExited => "This key has exited the trial".to_string(),
+            TrialError::TransactionLimitReached => "Transaction limit reached".to_string(),
+            TrialError::ChainNotAllowed { chain_id } => {
+                format!("Chain `{}` is not enabled for this trial", chain_id)
+            }
+            TrialError::UnknownChain { chain_id } => {
+                format!("Chain `{}` is not in the chain registry", chain_id)
+            }
+            TrialError::ChainDisabled { chain_id } => {
+                format!("Chain `{}` is disabled", chain_id)
+            }
+            TrialError::MethodNotAllowed { method } => {
+                format!("Method `{}` not allowed", method)
+            }
+            TrialError::ContractNotAllowed { contract } => {
+                format!("Contract `{}` not allowed", contract)
+            }
+            TrialError::MessageRecipientNotAllowed { recipient } => {
+                format!("Signing messages for `{}` is not allowed", recipient)
+            }
+            TrialError::PrimaryTypeNotAllowed { primary_type } => {
+                format!("Signing `{}` typed data is not allowed", primary_type)
+            }
+            TrialError::PersonalSignNotAllowed { chain_id } => {
+                format!("EIP-191 messages may not be signed on chain `{}`", chain_id)
+            }
+            TrialError::PaymasterNotAllowed { paymaster } => {
+                format!("Paymaster `{}` is not allowed", paymaster)
+            }
+            TrialError::PaymasterRequired => {
+                "User operations must be sponsored by a paymaster".to_string()
+            }
+            TrialError::EntryPointNotAllowed { entry_point } => {
+                format!("Entry point `{}` is not allowed", entry_point)
+            }
+            TrialError::GasExceedsLimit => "Attached gas exceeds maximum allowed".to_string(),
+            TrialError::DepositExceedsLimit => {
+                "Attached deposit exceeds maximum allowed".to_string()
+            }
+            TrialError::FeeRateExceedsLimit => "Fee rate exceeds maximum allowed".to_string(),
+            TrialError::EvmFeeExceedsLimit => "Fee per gas exceeds maximum allowed".to_string(),
+            TrialError::EvmFeeBudgetExceeded => {
+                "Transaction fees would exceed the key's fee budget".to_string()
+            }
+            TrialError::FeeExceedsChainCap { fee, cap } => {
+                format!("`{}` exceeds the chain's cap of {} wei", fee, cap)
+            }
+            TrialError::Eip1559NotSupported { chain_id } => {
+                format!("Chain `{}` does not support EIP-1559 transactions", chain_id)
+            }
+            TrialError::MissingGasLimit => {
+                "No gas limit was given and the chain has no default".to_string()
+            }
+            TrialError::MissingEvmFee { field } => {
+                format!("`{}` is required for this transaction type", field)
+            }
+            TrialError::InvalidChainId { chain_id } => format!("Invalid chain ID `{}`", chain_id),
+            TrialError::ConstraintsMismatch { chain_id, expected } => format!(
+                "Chain ID `{}` expects {} constraints",
+                chain_id, expected
+            ),
+            TrialError::InvalidNearAccountId { account_id } => {
+                format!("Invalid NEAR account ID `{}`", account_id)
+            }
+            TrialError::InvalidPublicKey { public_key } => {
+                format!("Invalid public key `{}`", public_key)
+            }
+            TrialError::InvalidEvmAddress { address } => {
+                format!("Invalid Ethereum address `{}`", address)
+            }
+            TrialError::InvalidBitcoinPublicKey { public_key } => {
+                format!("`{}` is not a secp256k1 public key", public_key)
+            }
+            TrialError::InvalidBitcoinScript { script } => {
+                format!("Invalid Bitcoin output script `{}`", script)
+            }
+            TrialError::InvalidBitcoinTransaction { reason } => {
+                format!("Invalid Bitcoin transaction: {}", reason)
+            }
+            TrialError::BitcoinAddressMismatch { address, expected } => format!(
+                "Bitcoin address `{}` does not match the MPC key, expected `{}`",
+                address, expected
+            ),
+            TrialError::AlreadyActivated { chain_id } => {
+                format!("The trial has already been activated on `{}`", chain_id)
+            }
+            TrialError::NotActivated { chain_id } => {
+                format!("Trial account not activated on `{}`", chain_id)
+            }
+            TrialError::NonceTooLow { nonce, last_nonce } => format!(
+                "Nonce {} must be greater than the last signed nonce {}",
+                nonce, last_nonce
+            ),
+            TrialError::DuplicatePayload => "This payload was already signed".to_string(),
+            TrialError::NoPayloadsToSign => "There is nothing to sign".to_string(),
+            TrialError::MpcSignatureFailed { index } => {
+                format!("The MPC contract did not return signature {}", index)
+            }
+            TrialError::InvalidMessageNonce => "Message nonce must be 32 bytes".to_string(),
+            TrialError::AmbiguousPersonalMessage => {
+                "32 byte messages can't be signed, as they may be a user operation hash".to_string()
+            }
+            TrialError::InsufficientDeposit { required } => {
+                format!("Insufficient deposit: required {} yoctoNEAR", required)
+            }
+            TrialError::InvalidAllowance => "Key allowance must be greater than zero".to_string(),
+            TrialError::InvalidAmount { amount } => format!("Invalid amount `{}`", amount),
+            TrialError::KeyAlreadyRegistered { public_key } => {
+                format!("Key `{}` is already registered", public_key)
+            }
+            TrialError::KeyNotOrphaned { public_key } => {
+                format!("Key `{}` still belongs to an existing trial", public_key)
+            }
+            TrialError::InsufficientTrialBalance {
+                required,
+                available,
+            } => format!(
+                "Insufficient trial balance: required {} yoctoNEAR, available {} yoctoNEAR",
+                required, available
+            ),
+            TrialError::InsufficientTreasuryBalance {
+                required,
+                available,
+            } => format!(
+                "Insufficient treasury balance: required {} yoctoNEAR, available {} yoctoNEAR",
+                required, available
+            ),
+            TrialError::ArithmeticOverflow => "Arithmetic overflow".to_string(),
+            TrialError::InvalidAbiValue { kind, value } => {
+                format!("Invalid {} value `{}`", kind, value)
+            }
+            TrialError::InvalidTypedData { reason } => format!("Invalid typed data: {}", reason),
+            TrialError::InvalidUserOperation { reason } => {
+                format!("Invalid user operation: {}", reason)
+            }
+            TrialError::AbiEncodingFailed => "Failed to encode input".to_string(),
+            TrialError::SerializationFailed => "Failed to serialize the payload".to_string(),
+        }
+    }
+
+    /// Aborts execution with the JSON encoded error.
+    pub fn panic(&self) -> ! {
+        env::panic_str(
+            &serde_json::json!({
+                "code": self.code(),
+                "message": self.message(),
+            })
+            .to_string(),
+        )
+    }
+}
+
+impl fmt::Display for TrialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
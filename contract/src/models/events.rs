@@ -0,0 +1,208 @@
+// models/events.rs
+use crate::*;
+
+/// NEP-297 standard name for events emitted by this contract.
+pub const EVENT_STANDARD: &str = "keypom_trials";
+
+/// Version of the event standard.
+pub const EVENT_VERSION: &str = "1.0.0";
+
+/// Events emitted as `EVENT_JSON:` logs following NEP-297.
+#[derive(Clone)]
+#[near(serializers = [json])]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum TrialEvent {
+    TrialCreated(Vec<TrialCreatedData>),
+    TrialDeleted(Vec<TrialDeletedData>),
+    KeysAdded(Vec<KeysAddedData>),
+    TrialActivated(Vec<TrialActivatedData>),
+    ActionSigned(Vec<ActionSignedData>),
+    Exit(Vec<TrialExitData>),
+    MpcContractUpdated(Vec<MpcContractUpdatedData>),
+    AdminProposed(Vec<AdminData>),
+    AdminTransferred(Vec<AdminData>),
+    ContractPaused(Vec<AdminData>),
+    ContractUnpaused(Vec<AdminData>),
+    TrialPaused(Vec<TrialStatusData>),
+    TrialResumed(Vec<TrialStatusData>),
+    KeyFrozen(Vec<KeyStatusData>),
+    KeyUnfrozen(Vec<KeyStatusData>),
+    KeysRemoved(Vec<KeysRemovedData>),
+    TrialConstraintsUpdated(Vec<TrialStatusData>),
+    ManagerAdded(Vec<TrialManagerData>),
+    ManagerRemoved(Vec<TrialManagerData>),
+    CreationPolicyUpdated(Vec<CreationPolicyData>),
+    CreatorsAllowed(Vec<CreatorsData>),
+    CreatorsDisallowed(Vec<CreatorsData>),
+    TreasuryWithdrawn(Vec<TreasuryWithdrawnData>),
+    KeyAllowanceUpdated(Vec<KeyAllowanceData>),
+    ChainUpdated(Vec<ChainUpdatedData>),
+    ChainRemoved(Vec<ChainRemovedData>),
+    KeyNonceResynced(Vec<KeyNonceData>),
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TrialCreatedData {
+    pub trial_id: TrialId,
+    pub creator_id: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TrialDeletedData {
+    pub trial_id: TrialId,
+    pub deleted_by: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct KeysAddedData {
+    pub trial_id: TrialId,
+    pub public_keys: Vec<PublicKey>,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct KeysRemovedData {
+    pub trial_id: TrialId,
+    pub public_keys: Vec<PublicKey>,
+    pub removed_by: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TrialActivatedData {
+    pub trial_id: TrialId,
+    pub public_key: PublicKey,
+    pub chain_id: ChainId,
+    pub account_id: String,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct ActionSignedData {
+    pub trial_id: TrialId,
+    pub public_key: PublicKey,
+    pub chain_id: ChainId,
+    pub method: String,
+    pub contract: String,
+    /// Hex encoded hash sent to the MPC contract.
+    pub payload_hash: String,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TrialExitData {
+    pub trial_id: TrialId,
+    pub public_key: PublicKey,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct MpcContractUpdatedData {
+    pub mpc_contract: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct AdminData {
+    pub admin_account: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TrialStatusData {
+    pub trial_id: TrialId,
+    pub updated_by: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct KeyStatusData {
+    pub trial_id: TrialId,
+    pub public_key: PublicKey,
+    pub updated_by: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TrialManagerData {
+    pub trial_id: TrialId,
+    pub account_id: AccountId,
+    pub roles: Vec<TrialRole>,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct CreationPolicyData {
+    pub policy: CreationPolicy,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct CreatorsData {
+    pub account_ids: Vec<AccountId>,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TreasuryWithdrawnData {
+    pub amount: NearToken,
+    pub receiver_id: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct KeyAllowanceData {
+    pub trial_id: TrialId,
+    pub public_key: PublicKey,
+    pub allowance: NearToken,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct ChainUpdatedData {
+    pub chain_id: ChainId,
+    pub metadata: ChainMetadata,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct ChainRemovedData {
+    pub chain_id: ChainId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct KeyNonceData {
+    pub trial_id: TrialId,
+    pub public_key: PublicKey,
+    pub chain_id: ChainId,
+    pub last_nonce: Option<U64>,
+    pub updated_by: AccountId,
+}
+
+/// NEP-297 envelope around an event.
+#[near(serializers = [json])]
+struct EventLog {
+    standard: String,
+    version: String,
+    #[serde(flatten)]
+    event: TrialEvent,
+}
+
+impl TrialEvent {
+    /// Logs the event as `EVENT_JSON:{...}`.
+    pub fn emit(self) {
+        let log = EventLog {
+            standard: EVENT_STANDARD.to_string(),
+            version: EVENT_VERSION.to_string(),
+            event: self,
+        };
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            serde_json::to_string(&log).unwrap()
+        ));
+    }
+}
@@ -10,6 +10,37 @@ use crate::*;
 pub enum UserAccountId {
     NEAR(AccountId),
     EVM(Address),
+    Bitcoin(BitcoinAccount),
+}
+
+/// A P2WPKH account controlled by the key's MPC derived public key.
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub struct BitcoinAccount {
+    pub address: String,       // Bech32 address
+    pub pubkey_hash: [u8; 20], // HASH160 of the compressed public key
+}
+
+impl BitcoinAccount {
+    /// Output script paying to this account.
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        [&[0x00, 0x14][..], &self.pubkey_hash].concat()
+    }
+
+    /// BIP-143 script code used when signing inputs spent from this account.
+    pub fn script_code(&self) -> Vec<u8> {
+        [&[0x76, 0xa9, 0x14][..], &self.pubkey_hash, &[0x88, 0xac]].concat()
+    }
+}
+
+impl std::fmt::Display for UserAccountId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserAccountId::NEAR(account_id) => write!(f, "{}", account_id),
+            UserAccountId::EVM(address) => write!(f, "{}", convert_address_to_hex_string(address)),
+            UserAccountId::Bitcoin(account) => write!(f, "{}", account.address),
+        }
+    }
 }
 
 /// Tracks usage statistics for trial accounts.
@@ -17,11 +48,13 @@ pub enum UserAccountId {
 #[near(serializers = [json, borsh])]
 pub struct UsageStats {
     pub total_interactions: u64,
-    pub interactions_per_day: HashMap<u64, u64>, // Day timestamp to interaction count
+    pub interactions_per_day: HashMap<u64, u64>, // Day index to interaction count
     pub methods_called: HashMap<String, u64>,    // method_name to count
     pub contracts_called: HashMap<String, u64>,  // contract_id or address to count
     pub gas_used: u128,
-    pub deposit_used: U128, // For NEAR, represents yoctoNEAR; for EVM, represents wei
+    pub deposit_used: U128,  // For NEAR, represents yoctoNEAR; for EVM, represents wei
+    /// Worst-case EVM fees in wei the account paid itself on each chain
+    pub evm_fees_used_by_chain_id: HashMap<ChainId, U128>,
 }
 // Implement default for UsageStats
 impl Default for UsageStats {
@@ -33,6 +66,7 @@ impl Default for UsageStats {
             contracts_called: HashMap::new(),
             gas_used: 0,
             deposit_used: U128(0),
+            evm_fees_used_by_chain_id: HashMap::new(),
         }
     }
 }
@@ -45,6 +79,13 @@ pub struct KeyUsage {
     pub mpc_key: PublicKey,
     pub account_id_by_chain_id: HashMap<ChainId, UserAccountId>,
     pub usage_stats: UsageStats,
+    pub frozen: bool,         // Set by the creator to stop signing for this key
+    pub exited: bool,         // Set once the user exits, after which only `exit_trial` signs
+    pub allowance: NearToken, // Gas allowance last granted to the access key
+    /// Highest nonce signed on each chain
+    pub last_nonce_by_chain_id: HashMap<ChainId, u64>,
+    /// Hashes of recently signed payloads, oldest first
+    pub recent_payload_hashes: Vec<CryptoHash>,
 }
 
 /// Structure representing a key with both a public key and MPC key
@@ -53,4 +94,75 @@ pub struct KeyUsage {
 pub struct KeyWithMPC {
     pub public_key: PublicKey,
     pub mpc_key: PublicKey,
+    pub allowance: Option<NearToken>, // Overrides the trial's key allowance
+}
+
+impl KeyUsage {
+    /// Returns the NEAR network and account this key was activated with.
+    /// A key can only be activated on one NEAR network.
+    pub fn near_account(&self) -> (NearNetwork, AccountId) {
+        self.account_id_by_chain_id
+            .iter()
+            .find_map(|(chain_id, account_id)| match (chain_id, account_id) {
+                (ChainId::Near { network }, UserAccountId::NEAR(account_id)) => {
+                    Some((*network, account_id.clone()))
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| {
+                TrialError::NotActivated {
+                    chain_id: "NEAR".to_string(),
+                }
+                .panic()
+            })
+    }
+
+    /// Records a payload about to be signed.
+    /// Panics if the nonce isn't above the last one signed on the chain, or if the
+    /// same payload was signed recently.
+    pub fn record_signed_payload(
+        &mut self,
+        chain_id: &ChainId,
+        nonce: Option<u64>,
+        payload_hash: CryptoHash,
+    ) {
+        if self.recent_payload_hashes.contains(&payload_hash) {
+            TrialError::DuplicatePayload.panic();
+        }
+        if let Some(nonce) = nonce {
+            if let Some(&last_nonce) = self.last_nonce_by_chain_id.get(chain_id) {
+                if nonce <= last_nonce {
+                    TrialError::NonceTooLow { nonce, last_nonce }.panic();
+                }
+            }
+            self.last_nonce_by_chain_id.insert(chain_id.clone(), nonce);
+        }
+
+        self.recent_payload_hashes.push(payload_hash);
+        if self.recent_payload_hashes.len() > RECENT_PAYLOAD_HASHES_LIMIT {
+            self.recent_payload_hashes.remove(0);
+        }
+    }
+
+    /// Returns the address this key was activated with on an EVM chain.
+    pub fn evm_account(&self, chain_id: &ChainId) -> Address {
+        match self.account_id_by_chain_id.get(chain_id) {
+            Some(UserAccountId::EVM(address)) => *address,
+            _ => TrialError::NotActivated {
+                chain_id: chain_id.to_string(),
+            }
+            .panic(),
+        }
+    }
+
+    /// Returns the Bitcoin account this key was activated with on a Bitcoin chain.
+    pub fn bitcoin_account(&self, chain_id: &ChainId) -> BitcoinAccount {
+        match self.account_id_by_chain_id.get(chain_id) {
+            Some(UserAccountId::Bitcoin(account)) => account.clone(),
+            _ => TrialError::NotActivated {
+                chain_id: chain_id.to_string(),
+            }
+            .panic(),
+        }
+    }
 }
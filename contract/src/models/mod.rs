@@ -2,19 +2,35 @@
 pub mod action;
 pub mod chain_constraints;
 pub mod chain_id;
+pub mod chain_metadata;
 pub mod constants;
 pub mod contract;
+pub mod creation_policy;
+pub mod errors;
+pub mod events;
 pub mod key_usage;
+pub mod patterns;
 pub mod sign_request;
 pub mod trial_data;
+pub mod trial_role;
+pub mod trial_stats;
 pub mod usage_constraints;
+pub mod versioned;
 
 pub use action::*;
 pub use chain_constraints::*;
 pub use chain_id::*;
+pub use chain_metadata::*;
 pub use constants::*;
 pub use contract::*;
+pub use creation_policy::*;
+pub use errors::*;
+pub use events::*;
 pub use key_usage::*;
+pub use patterns::*;
 pub use sign_request::*;
 pub use trial_data::*;
+pub use trial_role::*;
+pub use trial_stats::*;
 pub use usage_constraints::*;
+pub use versioned::*;
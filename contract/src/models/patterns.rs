@@ -0,0 +1,244 @@
+// models/patterns.rs
+use crate::*;
+use hex::FromHex;
+use near_sdk::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use omni_transaction::evm::types::Address;
+use std::str::FromStr;
+
+/// An entry in `allowed_methods`.
+///
+/// Parsed from its string form when the trial is created:
+/// - `*` matches any method
+/// - `nft_*` matches any method starting with `nft_`
+/// - anything else must match the method name exactly
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh])]
+pub enum MethodPattern {
+    Any,
+    Prefix(String),
+    Exact(String),
+}
+
+impl MethodPattern {
+    /// Checks if a method name matches this pattern.
+    pub fn matches(&self, method: &str) -> bool {
+        match self {
+            MethodPattern::Any => true,
+            MethodPattern::Prefix(prefix) => method.starts_with(prefix.as_str()),
+            MethodPattern::Exact(name) => method == name,
+        }
+    }
+}
+
+impl FromStr for MethodPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern == "*" {
+            return Ok(MethodPattern::Any);
+        }
+
+        // Only a single trailing wildcard is supported
+        let (name, is_prefix) = match pattern.strip_suffix('*') {
+            Some(prefix) => (prefix, true),
+            None => (pattern, false),
+        };
+        if name.is_empty() || name.contains('*') {
+            return Err(format!("Invalid method pattern `{}`", pattern));
+        }
+
+        if is_prefix {
+            Ok(MethodPattern::Prefix(name.to_string()))
+        } else {
+            Ok(MethodPattern::Exact(name.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for MethodPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MethodPattern::Any => write!(f, "*"),
+            MethodPattern::Prefix(prefix) => write!(f, "{}*", prefix),
+            MethodPattern::Exact(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// An entry in the NEAR `allowed_contracts` list.
+///
+/// Parsed from its string form when the trial is created:
+/// - `*` matches any account
+/// - `*.ref-finance.near` matches any sub-account of `ref-finance.near`
+///   (at any depth), but not `ref-finance.near` itself
+/// - anything else must be a valid account ID and match exactly
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh])]
+pub enum AccountPattern {
+    Any,
+    SubAccountOf(AccountId),
+    Exact(AccountId),
+}
+
+impl AccountPattern {
+    /// Checks if an account ID matches this pattern.
+    pub fn matches(&self, account_id: &str) -> bool {
+        match self {
+            AccountPattern::Any => true,
+            AccountPattern::SubAccountOf(parent) => account_id
+                .strip_suffix(parent.as_str())
+                .is_some_and(|rest| rest.len() > 1 && rest.ends_with('.')),
+            AccountPattern::Exact(exact) => account_id == exact.as_str(),
+        }
+    }
+}
+
+impl FromStr for AccountPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern == "*" {
+            return Ok(AccountPattern::Any);
+        }
+
+        let invalid = || format!("Invalid contract pattern `{}`", pattern);
+        match pattern.strip_prefix("*.") {
+            Some(parent) => parent
+                .parse()
+                .map(AccountPattern::SubAccountOf)
+                .map_err(|_| invalid()),
+            None => pattern
+                .parse()
+                .map(AccountPattern::Exact)
+                .map_err(|_| invalid()),
+        }
+    }
+}
+
+impl fmt::Display for AccountPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountPattern::Any => write!(f, "*"),
+            AccountPattern::SubAccountOf(parent) => write!(f, "*.{}", parent),
+            AccountPattern::Exact(exact) => write!(f, "{}", exact),
+        }
+    }
+}
+
+/// An entry in an EVM address allowlist, such as `allowed_paymasters`.
+///
+/// Parsed from its string form when the trial is created:
+/// - `*` matches any address
+/// - anything else must be a hex encoded address, matched case-insensitively
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh])]
+pub enum AddressPattern {
+    Any,
+    Exact(Address),
+}
+
+impl AddressPattern {
+    /// Checks if a hex encoded address matches this pattern.
+    pub fn matches(&self, address: &str) -> bool {
+        match self {
+            AddressPattern::Any => true,
+            AddressPattern::Exact(exact) => <[u8; 20]>::from_hex(address.trim_start_matches("0x"))
+                .is_ok_and(|bytes| Address::from(bytes) == *exact),
+        }
+    }
+}
+
+impl FromStr for AddressPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern == "*" {
+            return Ok(AddressPattern::Any);
+        }
+
+        <[u8; 20]>::from_hex(pattern.trim_start_matches("0x"))
+            .map(|bytes| AddressPattern::Exact(Address::from(bytes)))
+            .map_err(|_| format!("Invalid address pattern `{}`", pattern))
+    }
+}
+
+impl fmt::Display for AddressPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressPattern::Any => write!(f, "*"),
+            AddressPattern::Exact(address) => {
+                write!(f, "{}", convert_address_to_hex_string(address))
+            }
+        }
+    }
+}
+
+/// An entry in the Bitcoin `allowed_recipient_scripts` list.
+///
+/// Parsed from its string form when the trial is created:
+/// - `*` matches any output script
+/// - anything else must be a hex encoded `scriptPubKey` and match exactly
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[near(serializers = [borsh])]
+pub enum ScriptPattern {
+    Any,
+    Exact(Vec<u8>),
+}
+
+impl ScriptPattern {
+    /// Checks if a hex encoded output script matches this pattern.
+    pub fn matches(&self, script: &str) -> bool {
+        match self {
+            ScriptPattern::Any => true,
+            ScriptPattern::Exact(exact) => hex::decode(script).is_ok_and(|bytes| bytes == *exact),
+        }
+    }
+}
+
+impl FromStr for ScriptPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern == "*" {
+            return Ok(ScriptPattern::Any);
+        }
+
+        match hex::decode(pattern) {
+            Ok(bytes) if !bytes.is_empty() => Ok(ScriptPattern::Exact(bytes)),
+            _ => Err(format!("Invalid script pattern `{}`", pattern)),
+        }
+    }
+}
+
+impl fmt::Display for ScriptPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptPattern::Any => write!(f, "*"),
+            ScriptPattern::Exact(script) => write!(f, "{}", hex::encode(script)),
+        }
+    }
+}
+
+// Patterns are exchanged as plain strings in JSON so existing allowlists keep working
+macro_rules! impl_string_serde {
+    ($pattern:ty) => {
+        impl Serialize for $pattern {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.to_string())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $pattern {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let pattern = String::deserialize(deserializer)?;
+                pattern.parse().map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+impl_string_serde!(MethodPattern);
+impl_string_serde!(AccountPattern);
+impl_string_serde!(AddressPattern);
+impl_string_serde!(ScriptPattern);
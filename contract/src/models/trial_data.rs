@@ -1,5 +1,6 @@
 // models/trial_data.rs
 use crate::*;
+use hex::FromHex;
 use omni_transaction::evm::types::Address;
 use std::collections::HashMap;
 
@@ -12,9 +13,46 @@ pub struct TrialData {
     pub exit_conditions: Option<ExitConditions>,
     pub expiration_time: Option<u64>,
     pub creator_account_id: AccountId,
+    pub balance: NearToken,                           // Funds storage growth caused by trial keys
+    pub paused: bool,                                 // Stops all signing when set by the creator
+    pub managers: HashMap<AccountId, Vec<TrialRole>>, // Roles granted by the creator
+    pub key_allowance: Option<NearToken>,             // Gas allowance for each new key
+}
+
+/// Checks a value against an optional allowlist of patterns.
+/// Anything is allowed if there is no allowlist, and a missing value never is if there is.
+fn is_in_optional_allowlist<P>(
+    allowlist: &Option<Vec<P>>,
+    value: Option<&str>,
+    matches: impl Fn(&P, &str) -> bool,
+) -> bool {
+    match (allowlist, value) {
+        (Some(allowlist), Some(value)) => allowlist.iter().any(|pattern| matches(pattern, value)),
+        (Some(_), None) => false,
+        (None, _) => true,
+    }
+}
+
+/// Checks a value against an allowlist creators must opt in to.
+/// Nothing is allowed if there is no allowlist.
+fn is_in_opt_in_allowlist<P>(
+    allowlist: &Option<Vec<P>>,
+    value: Option<&str>,
+    matches: impl Fn(&P, &str) -> bool,
+) -> bool {
+    allowlist.is_some() && is_in_optional_allowlist(allowlist, value, matches)
 }
 
 impl TrialData {
+    /// Checks if an account is the creator or a manager holding the given role.
+    pub fn has_role(&self, account_id: &AccountId, role: TrialRole) -> bool {
+        *account_id == self.creator_account_id
+            || self
+                .managers
+                .get(account_id)
+                .is_some_and(|roles| roles.contains(&role))
+    }
+
     /// Retrieves the chain constraints for a given chain ID.
     pub fn get_chain_constraints(&self, chain_id: &ChainId) -> Option<&ChainConstraints> {
         self.constraints_by_chain_id.get(chain_id)
@@ -23,14 +61,15 @@ impl TrialData {
     /// Checks if a method is allowed on a given chain.
     pub fn is_method_allowed(&self, method: &str, chain_id: &ChainId) -> bool {
         if let Some(chain_constraints) = self.get_chain_constraints(chain_id) {
-            match chain_constraints {
-                ChainConstraints::NEAR(constraints) => {
-                    constraints.allowed_methods.contains(&method.to_string())
-                }
-                ChainConstraints::EVM(constraints) => {
-                    constraints.allowed_methods.contains(&method.to_string())
-                }
-            }
+            let allowed_methods = match chain_constraints {
+                ChainConstraints::NEAR(constraints) => &constraints.allowed_methods,
+                ChainConstraints::EVM(constraints) => &constraints.allowed_methods,
+                // Bitcoin only supports plain transfers
+                ChainConstraints::Bitcoin(_) => return method == BITCOIN_TRANSFER_METHOD,
+            };
+            allowed_methods
+                .iter()
+                .any(|pattern| pattern.matches(method))
         } else {
             false
         }
@@ -42,19 +81,27 @@ impl TrialData {
             match chain_constraints {
                 ChainConstraints::NEAR(constraints) => constraints
                     .allowed_contracts
-                    .contains(&contract.parse().unwrap()),
+                    .iter()
+                    .any(|pattern| pattern.matches(contract)),
                 ChainConstraints::EVM(constraints) => {
                     // Assuming contract is a hex string representing the address
                     let addr_str = contract.trim_start_matches("0x");
-                    let addr_bytes: [u8; 20] = match hex::decode(addr_str) {
-                        Ok(bytes) => bytes.try_into().expect("Invalid Ethereum address length"),
-                        Err(_) => {
+                    let addr_bytes: [u8; 20] = match hex::decode(addr_str)
+                        .ok()
+                        .and_then(|bytes| bytes.try_into().ok())
+                    {
+                        Some(bytes) => bytes,
+                        None => {
                             return false;
                         }
                     };
                     let address = Address::from(addr_bytes);
                     constraints.allowed_contracts.contains(&address)
                 }
+                ChainConstraints::Bitcoin(constraints) => constraints
+                    .allowed_recipient_scripts
+                    .iter()
+                    .any(|pattern| pattern.matches(contract)),
             }
         } else {
             false
@@ -79,6 +126,8 @@ impl TrialData {
                         true
                     }
                 }
+                // Bitcoin fees are capped by fee rate instead
+                ChainConstraints::Bitcoin(_) => true,
             }
         } else {
             false
@@ -103,12 +152,147 @@ impl TrialData {
                         true
                     }
                 }
+                ChainConstraints::Bitcoin(constraints) => {
+                    if let Some(max_amount) = constraints.max_amount {
+                        deposit <= max_amount.0 as u128
+                    } else {
+                        true
+                    }
+                }
             }
         } else {
             false
         }
     }
 
+    /// Checks if a Bitcoin fee rate (satoshis per vbyte) is within allowed limits.
+    pub fn is_fee_rate_within_limits(&self, fee_rate: u64, chain_id: &ChainId) -> bool {
+        match self.get_chain_constraints(chain_id) {
+            Some(ChainConstraints::Bitcoin(constraints)) => {
+                if let Some(max_fee_rate) = constraints.max_fee_rate {
+                    fee_rate <= max_fee_rate.0
+                } else {
+                    true
+                }
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks the EVM fee parameters against the per-gas caps.
+    pub fn are_evm_fees_within_limits(&self, evm_action: &EvmAction, chain_id: &ChainId) -> bool {
+        match self.get_chain_constraints(chain_id) {
+            Some(ChainConstraints::EVM(constraints)) => {
+                let max_fee_ok = match constraints.max_fee_per_gas_cap {
+                    Some(cap) => evm_action.max_fee_per_gas.0 <= cap.0,
+                    None => true,
+                };
+                let priority_fee_ok = match (
+                    constraints.max_priority_fee_cap,
+                    evm_action.max_priority_fee_per_gas,
+                ) {
+                    (Some(cap), Some(fee)) => fee.0 <= cap.0,
+                    _ => true,
+                };
+                max_fee_ok && priority_fee_ok
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks if a key that already spent `fees_used` on a chain can pay `fee` more there.
+    /// Fees are budgeted against the account's initial deposit on the chain.
+    pub fn is_within_evm_fee_budget(&self, fees_used: u128, fee: u128, chain_id: &ChainId) -> bool {
+        match self.get_chain_constraints(chain_id) {
+            Some(ChainConstraints::EVM(constraints)) => {
+                fees_used.saturating_add(fee) <= constraints.initial_deposit.0
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks if a user operation with the given paymaster, if any, may be signed on a chain.
+    pub fn is_paymaster_allowed(&self, paymaster: Option<&str>, chain_id: &ChainId) -> bool {
+        let Some(ChainConstraints::EVM(constraints)) = self.get_chain_constraints(chain_id) else {
+            return false;
+        };
+        is_in_optional_allowlist(
+            &constraints.allowed_paymasters,
+            paymaster,
+            AddressPattern::matches,
+        )
+    }
+
+    /// Checks if a user operation may be sent through an entry point on a given chain.
+    pub fn is_entry_point_allowed(&self, entry_point: &str, chain_id: &ChainId) -> bool {
+        match self.get_chain_constraints(chain_id) {
+            Some(ChainConstraints::EVM(constraints)) => constraints
+                .allowed_entry_points
+                .as_ref()
+                .is_some_and(|allowed| allowed.iter().any(|pattern| pattern.matches(entry_point))),
+            _ => false,
+        }
+    }
+
+    /// Checks if the call a user operation executes may use a selector on a given chain.
+    /// Plain transfers have no selector and are always allowed.
+    pub fn is_selector_allowed(&self, selector: &str, chain_id: &ChainId) -> bool {
+        if selector.is_empty() {
+            return true;
+        }
+        match self.get_chain_constraints(chain_id) {
+            Some(ChainConstraints::EVM(constraints)) => {
+                <[u8; 4]>::from_hex(selector.trim_start_matches("0x"))
+                    .is_ok_and(|selector| constraints.allowed_selectors.contains(&selector))
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks if off-chain messages may be signed for a recipient on a given chain.
+    /// The recipient is the NEP-413 recipient or the EIP-712 verifying contract.
+    /// Neither can be signed for any recipient unless the creator allows it.
+    pub fn is_message_recipient_allowed(
+        &self,
+        recipient: Option<&str>,
+        chain_id: &ChainId,
+    ) -> bool {
+        match self.get_chain_constraints(chain_id) {
+            Some(ChainConstraints::NEAR(constraints)) => is_in_opt_in_allowlist(
+                &constraints.allowed_message_recipients,
+                recipient,
+                AccountPattern::matches,
+            ),
+            Some(ChainConstraints::EVM(constraints)) => is_in_opt_in_allowlist(
+                &constraints.allowed_verifying_contracts,
+                recipient,
+                AddressPattern::matches,
+            ),
+            _ => false,
+        }
+    }
+
+    /// Checks if EIP-191 messages may be signed on a given chain.
+    pub fn is_personal_sign_allowed(&self, chain_id: &ChainId) -> bool {
+        match self.get_chain_constraints(chain_id) {
+            Some(ChainConstraints::EVM(constraints)) => constraints.allow_personal_sign,
+            _ => false,
+        }
+    }
+
+    /// Checks if EIP-712 data of a primary type may be signed on a given chain.
+    /// No type can be signed unless the creator allows it.
+    pub fn is_primary_type_allowed(&self, primary_type: &str, chain_id: &ChainId) -> bool {
+        match self.get_chain_constraints(chain_id) {
+            Some(ChainConstraints::EVM(constraints)) => is_in_opt_in_allowlist(
+                &constraints.allowed_primary_types,
+                Some(primary_type),
+                MethodPattern::matches,
+            ),
+            _ => false,
+        }
+    }
+
     /// Checks if the trial has expired.
     pub fn has_expired(&self, current_timestamp: u64) -> bool {
         if let Some(expiration_time) = self.expiration_time {
@@ -131,3 +315,83 @@ impl TrialData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::accounts;
+
+    fn trial_with(chain_id: ChainId, constraints: ChainConstraints) -> TrialData {
+        TrialData {
+            constraints_by_chain_id: HashMap::from([(chain_id, constraints)]),
+            usage_constraints: None,
+            interaction_limits: None,
+            exit_conditions: None,
+            expiration_time: None,
+            creator_account_id: accounts(1),
+            balance: NearToken::from_near(0),
+            paused: false,
+            managers: HashMap::new(),
+            key_allowance: None,
+        }
+    }
+
+    #[test]
+    fn near_messages_need_an_allowed_recipient() {
+        let chain_id = ChainId::Near {
+            network: NearNetwork::Testnet,
+        };
+        let mut constraints = NearConstraints {
+            allowed_methods: Vec::new(),
+            allowed_contracts: Vec::new(),
+            max_gas: None,
+            max_deposit: None,
+            initial_deposit: NearToken::from_near(0),
+            allowed_message_recipients: None,
+        };
+
+        let trial_data = trial_with(
+            chain_id.clone(),
+            ChainConstraints::NEAR(constraints.clone()),
+        );
+        assert!(!trial_data.is_message_recipient_allowed(Some("app.near"), &chain_id));
+
+        constraints.allowed_message_recipients =
+            Some(vec![AccountPattern::Exact("app.near".parse().unwrap())]);
+        let trial_data = trial_with(chain_id.clone(), ChainConstraints::NEAR(constraints));
+        assert!(trial_data.is_message_recipient_allowed(Some("app.near"), &chain_id));
+        assert!(!trial_data.is_message_recipient_allowed(Some("evil.near"), &chain_id));
+    }
+    #[test]
+    fn typed_data_needs_an_allowed_verifying_contract_and_type() {
+        let chain_id = ChainId::Evm(1);
+        let mut constraints = EvmConstraints {
+            allowed_methods: Vec::new(),
+            allowed_contracts: Vec::new(),
+            allowed_selectors: Vec::new(),
+            max_gas: None,
+            max_value: None,
+            initial_deposit: U128(0),
+            max_fee_per_gas_cap: None,
+            max_priority_fee_cap: None,
+            allow_personal_sign: false,
+            allowed_verifying_contracts: None,
+            allowed_primary_types: None,
+            allowed_paymasters: None,
+            allowed_entry_points: None,
+        };
+        let permit2 = "0x000000000022d473030f116ddee9f6b43ac78ba3";
+
+        let trial_data = trial_with(chain_id.clone(), ChainConstraints::EVM(constraints.clone()));
+        assert!(!trial_data.is_message_recipient_allowed(Some(permit2), &chain_id));
+        assert!(!trial_data.is_primary_type_allowed("PermitSingle", &chain_id));
+
+        constraints.allowed_verifying_contracts = Some(vec![AddressPattern::Any]);
+        constraints.allowed_primary_types = Some(vec![MethodPattern::Exact("Mail".to_string())]);
+        let trial_data = trial_with(chain_id.clone(), ChainConstraints::EVM(constraints));
+        assert!(trial_data.is_message_recipient_allowed(Some(permit2), &chain_id));
+        assert!(trial_data.is_primary_type_allowed("Mail", &chain_id));
+        assert!(!trial_data.is_primary_type_allowed("PermitSingle", &chain_id));
+    }
+}
@@ -0,0 +1,27 @@
+// models/trial_role.rs
+use crate::*;
+use std::fmt;
+
+/// Scoped permissions the creator can grant to a trial manager.
+/// The creator implicitly holds every role.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+pub enum TrialRole {
+    /// Can add and remove trial keys
+    KeyIssuer,
+    /// Can pause the trial, freeze keys and update constraints
+    Operator,
+    /// Can delete the trial and withdraw its balance
+    Owner,
+}
+
+impl fmt::Display for TrialRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrialRole::KeyIssuer => write!(f, "key_issuer"),
+            TrialRole::Operator => write!(f, "operator"),
+            TrialRole::Owner => write!(f, "owner"),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// models/trial_stats.rs
+use crate::*;
+use near_sdk::json_types::U128;
+
+/// Running usage aggregates across every key of a trial.
+#[derive(Clone, Default)]
+#[near(serializers = [json, borsh])]
+pub struct TrialStats {
+    pub keys_issued: u64,
+    pub keys_activated_by_chain_id: HashMap<ChainId, u64>,
+    pub keys_exited: u64,
+    pub total_interactions: u64,
+    pub gas_used_by_chain_id: HashMap<ChainId, U128>, // In each chain's gas units
+    pub deposit_used_by_chain_id: HashMap<ChainId, U128>, // yoctoNEAR, wei or satoshis
+    pub methods_called: HashMap<String, u64>, // method_name to count
+    pub daily_active_keys: HashMap<u64, u64>, // Day index to number of active keys
+}
+
+impl TrialStats {
+    /// Records an allowed action. `is_new_active_key` marks the key's first action of the day.
+    pub fn record_action(&mut self, action: &Action, day: u64, is_new_active_key: bool) {
+        self.total_interactions += 1;
+        let chain_id = action.chain_id();
+        let gas_used = self.gas_used_by_chain_id.entry(chain_id.clone()).or_insert(U128(0));
+        gas_used.0 = gas_used.0.saturating_add(action.gas());
+        let deposit_used = self.deposit_used_by_chain_id.entry(chain_id).or_insert(U128(0));
+        deposit_used.0 = deposit_used.0.saturating_add(action.deposit());
+        *self
+            .methods_called
+            .entry(action.method_name().to_string())
+            .or_insert(0) += 1;
+
+        prune_day_buckets(&mut self.daily_active_keys, day);
+        if is_new_active_key {
+            *self.daily_active_keys.entry(day).or_insert(0) += 1;
+        }
+    }
+
+    /// Records a key being activated on a chain.
+    pub fn record_activation(&mut self, chain_id: &ChainId) {
+        *self
+            .keys_activated_by_chain_id
+            .entry(chain_id.clone())
+            .or_insert(0) += 1;
+    }
+}
+
+/// Number of calls to a single method.
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct MethodCount {
+    pub method_name: String,
+    pub count: u64,
+}
+
+/// Trial aggregates as returned by `get_trial_stats`.
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct ExtTrialStats {
+    pub keys_issued: u64,
+    pub keys_activated_by_chain_id: HashMap<ChainId, u64>,
+    pub keys_exited: u64,
+    pub total_interactions: u64,
+    pub gas_used_by_chain_id: HashMap<ChainId, U128>,
+    pub deposit_used_by_chain_id: HashMap<ChainId, U128>,
+    pub top_methods: Vec<MethodCount>, // Most called methods first
+    pub daily_active_keys: HashMap<u64, u64>,
+}
+
+impl From<TrialStats> for ExtTrialStats {
+    fn from(stats: TrialStats) -> Self {
+        let mut top_methods: Vec<MethodCount> = stats
+            .methods_called
+            .into_iter()
+            .map(|(method_name, count)| MethodCount { method_name, count })
+            .collect();
+        top_methods.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.method_name.cmp(&b.method_name))
+        });
+        top_methods.truncate(TOP_METHODS_LIMIT);
+
+        Self {
+            keys_issued: stats.keys_issued,
+            keys_activated_by_chain_id: stats.keys_activated_by_chain_id,
+            keys_exited: stats.keys_exited,
+            total_interactions: stats.total_interactions,
+            gas_used_by_chain_id: stats.gas_used_by_chain_id,
+            deposit_used_by_chain_id: stats.deposit_used_by_chain_id,
+            top_methods,
+            daily_active_keys: stats.daily_active_keys,
+        }
+    }
+}
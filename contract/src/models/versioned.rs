@@ -0,0 +1,265 @@
+use crate::*;
+use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use omni_transaction::evm::types::Address;
+
+/// `TrialData` layout of the first deployed version, stored without a version tag.
+#[derive(Clone)]
+#[near(serializers = [borsh])]
+pub struct TrialDataV1 {
+    pub constraints_by_chain_id: HashMap<String, ChainConstraintsV1>,
+    pub usage_constraints: Option<UsageConstraints>,
+    pub interaction_limits: Option<InteractionLimits>,
+    pub exit_conditions: Option<ExitConditions>,
+    pub expiration_time: Option<u64>,
+    pub creator_account_id: AccountId,
+}
+
+#[derive(Clone)]
+#[near(serializers = [borsh])]
+pub enum ChainConstraintsV1 {
+    NEAR(NearConstraintsV1),
+    EVM(EvmConstraintsV1),
+}
+
+#[derive(Clone)]
+#[near(serializers = [borsh])]
+pub struct NearConstraintsV1 {
+    pub allowed_methods: Vec<String>,
+    pub allowed_contracts: Vec<AccountId>,
+    pub max_gas: Option<Gas>,
+    pub max_deposit: Option<NearToken>,
+    pub initial_deposit: NearToken,
+}
+
+#[derive(Clone)]
+#[near(serializers = [borsh])]
+pub struct EvmConstraintsV1 {
+    pub allowed_methods: Vec<String>,
+    pub allowed_contracts: Vec<Address>,
+    pub max_gas: Option<u64>,
+    pub max_value: Option<U128>,
+    pub initial_deposit: String, // Wei as a decimal string
+}
+
+/// `KeyUsage` layout of the first deployed version, stored without a version tag.
+#[derive(Clone)]
+#[near(serializers = [borsh])]
+pub struct KeyUsageV1 {
+    pub trial_id: TrialId,
+    pub mpc_key: PublicKey,
+    pub account_id_by_chain_id: HashMap<String, UserAccountId>,
+    pub usage_stats: UsageStatsV1,
+}
+
+#[derive(Clone)]
+#[near(serializers = [borsh])]
+pub struct UsageStatsV1 {
+    pub total_interactions: u64,
+    pub interactions_per_day: HashMap<u64, u64>,
+    pub methods_called: HashMap<String, u64>,
+    pub contracts_called: HashMap<String, u64>,
+    pub gas_used: u128,
+    pub deposit_used: U128,
+}
+
+/// Parses the free-form chain IDs of the first version, dropping any that no longer parse.
+fn parse_legacy_chain_ids<V, W>(
+    entries: HashMap<String, V>,
+    convert: impl Fn(V) -> W,
+) -> HashMap<ChainId, W> {
+    entries
+        .into_iter()
+        .filter_map(|(chain_id, value)| Some((chain_id.parse().ok()?, convert(value))))
+        .collect()
+}
+
+impl From<ChainConstraintsV1> for ChainConstraints {
+    fn from(constraints: ChainConstraintsV1) -> Self {
+        match constraints {
+            // The first version only matched exact method names and contracts
+            ChainConstraintsV1::NEAR(constraints) => ChainConstraints::NEAR(NearConstraints {
+                allowed_methods: constraints
+                    .allowed_methods
+                    .into_iter()
+                    .map(MethodPattern::Exact)
+                    .collect(),
+                allowed_contracts: constraints
+                    .allowed_contracts
+                    .into_iter()
+                    .map(AccountPattern::Exact)
+                    .collect(),
+                max_gas: constraints.max_gas,
+                max_deposit: constraints.max_deposit,
+                initial_deposit: constraints.initial_deposit,
+                // The first version couldn't sign messages, so none are allowed
+                allowed_message_recipients: None,
+            }),
+            // Deposits that don't parse were never funded, so they leave no fee budget
+            ChainConstraintsV1::EVM(constraints) => ChainConstraints::EVM(EvmConstraints {
+                allowed_methods: constraints
+                    .allowed_methods
+                    .into_iter()
+                    .map(MethodPattern::Exact)
+                    .collect(),
+                allowed_contracts: constraints.allowed_contracts,
+                allowed_selectors: Vec::new(),
+                max_gas: constraints.max_gas,
+                max_value: constraints.max_value,
+                initial_deposit: U128(constraints.initial_deposit.parse().unwrap_or(0)),
+                max_fee_per_gas_cap: None,
+                max_priority_fee_cap: None,
+                // The first version couldn't sign messages, so none are allowed
+                allow_personal_sign: false,
+                allowed_verifying_contracts: None,
+                allowed_primary_types: None,
+                allowed_paymasters: None,
+                allowed_entry_points: None,
+            }),
+        }
+    }
+}
+
+impl From<TrialDataV1> for TrialData {
+    fn from(trial_data: TrialDataV1) -> Self {
+        Self {
+            constraints_by_chain_id: parse_legacy_chain_ids(
+                trial_data.constraints_by_chain_id,
+                ChainConstraints::from,
+            ),
+            usage_constraints: trial_data.usage_constraints,
+            interaction_limits: trial_data.interaction_limits,
+            exit_conditions: trial_data.exit_conditions,
+            expiration_time: trial_data.expiration_time,
+            creator_account_id: trial_data.creator_account_id,
+            balance: NearToken::from_yoctonear(0),
+            paused: false,
+            managers: HashMap::new(),
+            key_allowance: None,
+        }
+    }
+}
+
+impl From<KeyUsageV1> for KeyUsage {
+    fn from(key_usage: KeyUsageV1) -> Self {
+        let usage_stats = key_usage.usage_stats;
+        Self {
+            trial_id: key_usage.trial_id,
+            mpc_key: key_usage.mpc_key,
+            account_id_by_chain_id: parse_legacy_chain_ids(
+                key_usage.account_id_by_chain_id,
+                |account_id| account_id,
+            ),
+            usage_stats: UsageStats {
+                total_interactions: usage_stats.total_interactions,
+                interactions_per_day: usage_stats.interactions_per_day,
+                methods_called: usage_stats.methods_called,
+                contracts_called: usage_stats.contracts_called,
+                gas_used: usage_stats.gas_used,
+                deposit_used: usage_stats.deposit_used,
+                evm_fees_used_by_chain_id: HashMap::new(),
+            },
+            frozen: false,
+            exited: false,
+            allowance: NearToken::from_yoctonear(0),
+            last_nonce_by_chain_id: HashMap::new(),
+            recent_payload_hashes: Vec::new(),
+        }
+    }
+}
+
+/// Stored layout of `TrialData`.
+///
+/// When `TrialData` changes, the previous layout is kept here as a new variant
+/// and converted to the current one the next time the entry is read.
+#[near(serializers = [borsh])]
+pub enum VersionedTrialData {
+    V1(TrialDataV1),
+    V2(TrialData),
+}
+
+impl VersionedTrialData {
+    /// Returns the current layout, upgrading older layouts in place.
+    pub fn upgrade(&mut self) -> &mut TrialData {
+        if let VersionedTrialData::V1(trial_data) = self {
+            *self = VersionedTrialData::V2(trial_data.clone().into());
+        }
+        match self {
+            VersionedTrialData::V2(trial_data) => trial_data,
+            VersionedTrialData::V1(_) => unreachable!(),
+        }
+    }
+
+    /// Returns a copy of the entry in the current layout.
+    pub fn to_current(&self) -> TrialData {
+        match self {
+            VersionedTrialData::V1(trial_data) => trial_data.clone().into(),
+            VersionedTrialData::V2(trial_data) => trial_data.clone(),
+        }
+    }
+}
+
+impl From<TrialData> for VersionedTrialData {
+    fn from(trial_data: TrialData) -> Self {
+        VersionedTrialData::V2(trial_data)
+    }
+}
+
+/// Stored layout of `KeyUsage`.
+///
+/// When `KeyUsage` changes, the previous layout is kept here as a new variant
+/// and converted to the current one the next time the entry is read.
+#[near(serializers = [borsh])]
+pub enum VersionedKeyUsage {
+    V1(KeyUsageV1),
+    V2(KeyUsage),
+}
+
+impl VersionedKeyUsage {
+    /// Returns the current layout, upgrading older layouts in place.
+    pub fn upgrade(&mut self) -> &mut KeyUsage {
+        if let VersionedKeyUsage::V1(key_usage) = self {
+            *self = VersionedKeyUsage::V2(key_usage.clone().into());
+        }
+        match self {
+            VersionedKeyUsage::V2(key_usage) => key_usage,
+            VersionedKeyUsage::V1(_) => unreachable!(),
+        }
+    }
+
+    /// Returns a copy of the entry in the current layout.
+    pub fn to_current(&self) -> KeyUsage {
+        match self {
+            VersionedKeyUsage::V1(key_usage) => key_usage.clone().into(),
+            VersionedKeyUsage::V2(key_usage) => key_usage.clone(),
+        }
+    }
+}
+
+impl From<KeyUsage> for VersionedKeyUsage {
+    fn from(key_usage: KeyUsage) -> Self {
+        VersionedKeyUsage::V2(key_usage)
+    }
+}
+
+/// Reads an entry written before entries were versioned. These have no version tag
+/// and stay under the collection's original prefix until they're next modified.
+pub fn read_legacy_entry<K, V>(prefix: StorageKeys, key: &K) -> Option<V>
+where
+    K: BorshSerialize + Ord + Clone,
+    V: BorshSerialize + BorshDeserialize + Clone,
+{
+    LookupMap::<K, V>::new(prefix).get(key).cloned()
+}
+
+/// Removes an entry written before entries were versioned, returning it.
+pub fn take_legacy_entry<K, V>(prefix: StorageKeys, key: &K) -> Option<V>
+where
+    K: BorshSerialize + Ord + Clone,
+    V: BorshSerialize + BorshDeserialize,
+{
+    let mut legacy_entries = LookupMap::<K, V>::new(prefix);
+    let entry = legacy_entries.remove(key);
+    legacy_entries.flush();
+    entry
+}
@@ -5,25 +5,35 @@ use crate::*;
 impl Contract {
     /// Associates public keys with a trial account in batch.
     /// Takes an array of objects containing both the public key and the corresponding MPC key.
+    /// Each key's gas allowance and storage are paid from the trial's balance, while the
+    /// platform's key fee, if any, is paid from the attached deposit.
+    /// Keys already registered to any trial are rejected.
     #[payable]
     pub fn add_trial_keys(&mut self, keys: Vec<KeyWithMPC>, trial_id: TrialId) {
-        let trial_data = self
-            .trial_data_by_id
-            .get(&trial_id)
-            .expect("Trial ID does not exist");
+        self.assert_not_paused();
 
-        // Only the creator can add keys to their trial
-        require!(
-            env::predecessor_account_id() == trial_data.creator_account_id,
-            "Only the trial creator can add keys"
-        );
+        let trial_data = self.assert_trial_role(trial_id, TrialRole::KeyIssuer);
 
         let initial_storage = env::storage_usage();
+        let mut total_allowance = NearToken::from_yoctonear(0);
 
         // Iterate through each object containing the public key and the MPC key
         for key_with_mpc in keys.iter() {
             let public_key = &key_with_mpc.public_key;
             let mpc_key = &key_with_mpc.mpc_key;
+            if self.internal_get_key_usage(public_key).is_some() {
+                TrialError::KeyAlreadyRegistered {
+                    public_key: public_key_to_string(public_key),
+                }
+                .panic();
+            }
+            let allowance = key_with_mpc
+                .allowance
+                .or(trial_data.key_allowance)
+                .unwrap_or(DEFAULT_KEY_ALLOWANCE);
+            total_allowance = total_allowance
+                .checked_add(allowance)
+                .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
 
             // Create the key usage for the trial
             let key_usage = KeyUsage {
@@ -31,22 +41,47 @@ impl Contract {
                 account_id_by_chain_id: HashMap::new(),
                 mpc_key: mpc_key.clone(),
                 usage_stats: UsageStats::default(),
+                frozen: false,
+                exited: false,
+                allowance,
+                last_nonce_by_chain_id: HashMap::new(),
+                recent_payload_hashes: Vec::new(),
             };
 
             // Store the public key in the trial data
-            self.key_usage_by_pk.insert(public_key.clone(), key_usage);
+            self.key_usage_by_pk.insert(public_key.clone(), key_usage.into());
 
             // Add the access key to the contract with limited permissions
             Promise::new(env::current_account_id()).add_access_key_allowance(
                 public_key.clone(),
-                Allowance::Unlimited, // Set allowance as needed
+                Allowance::limited(allowance)
+                    .unwrap_or_else(|| TrialError::InvalidAllowance.panic()),
                 env::current_account_id(),
-                TRIAL_ACCESS_KEY_METHODS.to_string(),
+                trial_access_key_methods(),
             );
         }
 
         self.key_usage_by_pk.flush();
-        // Adjust the deposit based on storage usage
-        self.adjust_deposit(initial_storage, env::storage_usage());
+        self.internal_debit_trial_balance(trial_id, total_allowance);
+
+        let public_keys: Vec<PublicKey> = keys.into_iter().map(|key| key.public_key).collect();
+        self.internal_add_keys_to_trial(trial_id, &public_keys);
+
+        if let Some(trial_stats) = self.trial_stats_by_id.get_mut(&trial_id) {
+            trial_stats.keys_issued += public_keys.len() as u64;
+        }
+        self.trial_stats_by_id.flush();
+
+        // Storage is paid for by the trial, the platform fee by the caller
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
+        self.trial_data_by_id.flush();
+        let key_fee = self.internal_key_fee(public_keys.len());
+        self.internal_collect_attached_fee(key_fee);
+
+        TrialEvent::KeysAdded(vec![KeysAddedData {
+            trial_id,
+            public_keys,
+        }])
+        .emit();
     }
 }
@@ -0,0 +1,100 @@
+// trial_creator/allowance.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Re-adds a trial key with a new gas allowance, paid in full from the trial's balance.
+    /// Whatever the key had left of its previous allowance is replaced, not added to.
+    /// Only callable by the creator or a key issuer manager.
+    pub fn top_up_key_allowance(&mut self, public_key: PublicKey, allowance: NearToken) {
+        self.assert_not_paused();
+
+        let key_usage = self
+            .internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+        let trial_id = key_usage.trial_id;
+        self.assert_trial_role(trial_id, TrialRole::KeyIssuer);
+
+        let access_key_allowance =
+            Allowance::limited(allowance).unwrap_or_else(|| TrialError::InvalidAllowance.panic());
+        self.internal_debit_trial_balance(trial_id, allowance);
+        self.trial_data_by_id.flush();
+        if let Some(key_usage) = self.internal_get_key_usage_mut(&public_key) {
+            key_usage.allowance = allowance;
+        }
+        self.key_usage_by_pk.flush();
+
+        // An access key's allowance can't be changed in place, so the key is replaced
+        Promise::new(env::current_account_id())
+            .delete_key(public_key.clone())
+            .add_access_key_allowance(
+                public_key.clone(),
+                access_key_allowance,
+                env::current_account_id(),
+                trial_access_key_methods(),
+            );
+
+        TrialEvent::KeyAllowanceUpdated(vec![KeyAllowanceData {
+            trial_id,
+            public_key,
+            allowance,
+        }])
+        .emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn top_up_debits_the_full_allowance_every_time() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .build());
+
+        let mut contract = Contract::new(accounts(0), accounts(2));
+        let public_key: PublicKey = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+            .parse()
+            .unwrap();
+        let trial_data = TrialData {
+            constraints_by_chain_id: HashMap::new(),
+            usage_constraints: None,
+            interaction_limits: None,
+            exit_conditions: None,
+            expiration_time: None,
+            creator_account_id: accounts(1),
+            balance: NearToken::from_near(10),
+            paused: false,
+            managers: HashMap::new(),
+            key_allowance: None,
+        };
+        let key_usage = KeyUsage {
+            trial_id: 1,
+            mpc_key: public_key.clone(),
+            account_id_by_chain_id: HashMap::new(),
+            usage_stats: UsageStats::default(),
+            frozen: false,
+            exited: false,
+            allowance: NearToken::from_near(1),
+            last_nonce_by_chain_id: HashMap::new(),
+            recent_payload_hashes: Vec::new(),
+        };
+        contract.trial_data_by_id.insert(1, trial_data.into());
+        contract
+            .key_usage_by_pk
+            .insert(public_key.clone(), key_usage.into());
+
+        // The same allowance twice still pays for two full allowances
+        contract.top_up_key_allowance(public_key.clone(), NearToken::from_near(1));
+        contract.top_up_key_allowance(public_key.clone(), NearToken::from_near(1));
+
+        let trial_data = contract.internal_get_trial(1).unwrap();
+        assert_eq!(trial_data.balance, NearToken::from_near(8));
+        let key_usage = contract.internal_get_key_usage(&public_key).unwrap();
+        assert_eq!(key_usage.allowance, NearToken::from_near(1));
+    }
+}
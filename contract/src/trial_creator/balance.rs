@@ -0,0 +1,46 @@
+// trial_creator/balance.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Adds the attached deposit to a trial's balance.
+    #[payable]
+    pub fn fund_trial(&mut self, trial_id: TrialId) -> NearToken {
+        let trial_data = self
+            .internal_get_trial_mut(trial_id)
+            .unwrap_or_else(|| TrialError::TrialNotFound { trial_id }.panic());
+
+        trial_data.balance = trial_data
+            .balance
+            .checked_add(env::attached_deposit())
+            .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
+        trial_data.balance
+    }
+
+    /// Withdraws from a trial's balance. Withdraws everything if no amount is given.
+    /// Only callable by the creator or an owner manager. The funds always go to the creator.
+    pub fn withdraw_trial_balance(
+        &mut self,
+        trial_id: TrialId,
+        amount: Option<NearToken>,
+    ) -> Promise {
+        let creator_account_id = self
+            .assert_trial_role(trial_id, TrialRole::Owner)
+            .creator_account_id;
+        let trial_data = self
+            .internal_get_trial_mut(trial_id)
+            .unwrap_or_else(|| TrialError::TrialNotFound { trial_id }.panic());
+
+        let available = trial_data.balance;
+        let amount = amount.unwrap_or(available);
+        trial_data.balance = available.checked_sub(amount).unwrap_or_else(|| {
+            TrialError::InsufficientTrialBalance {
+                required: amount.as_yoctonear(),
+                available: available.as_yoctonear(),
+            }
+            .panic()
+        });
+
+        Promise::new(creator_account_id).transfer(amount)
+    }
+}
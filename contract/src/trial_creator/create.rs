@@ -1,68 +1,30 @@
 // trial_creator/create.rs
 use crate::*;
-use hex::FromHex;
-use omni_transaction::evm::types::Address;
 
 #[near]
 impl Contract {
+    /// Creates a new trial owned by the caller.
+    /// Whatever is attached beyond the storage cost and any platform fee becomes the
+    /// trial's balance, which pays for storage and gas allowances of the trial's keys.
     #[payable]
     pub fn create_trial(
         &mut self,
-        chain_constraints: HashMap<String, ExtChainConstraints>,
+        chain_constraints: HashMap<ChainId, ExtChainConstraints>,
         usage_constraints: Option<UsageConstraints>,
         interaction_limits: Option<InteractionLimits>,
         exit_conditions: Option<ExitConditions>,
         expiration_time: Option<u64>,
+        key_allowance: Option<NearToken>,
     ) -> TrialId {
-        let creator_account_id = env::predecessor_account_id();
+        assert_not_self_call();
 
-        let mut constraints_by_chain_id = HashMap::new();
+        let creator_account_id = env::predecessor_account_id();
+        let trial_fee = self.internal_trial_fee(&creator_account_id);
 
-        for (chain_id_str, ext_constraints) in chain_constraints {
-            let chain_id = ChainId(chain_id_str.clone());
-            let chain_constraints = if chain_id.is_near() {
-                match ext_constraints {
-                    ExtChainConstraints::NEAR(near_constraints) => {
-                        ChainConstraints::NEAR(near_constraints)
-                    }
-                    _ => env::panic_str(&format!(
-                        "Chain ID `{}` expects NEAR constraints, found EVM constraints.",
-                        chain_id_str.clone()
-                    )),
-                }
-            } else if let Some(_chain_id_num) = chain_id.as_evm_chain_id() {
-                match ext_constraints {
-                    ExtChainConstraints::EVM(ext_evm_constraints) => {
-                        // Process EVM constraints as before
-                        let allowed_addresses = ext_evm_constraints
-                            .allowed_contracts
-                            .iter()
-                            .map(|addr_str| {
-                                let addr_str = addr_str.trim_start_matches("0x");
-                                let addr_bytes: [u8; 20] = <[u8; 20]>::from_hex(addr_str)
-                                    .expect("Invalid Ethereum address in allowed_contracts");
-                                Address::from(addr_bytes)
-                            })
-                            .collect();
-                        let evm_constraints = EvmConstraints {
-                            allowed_methods: ext_evm_constraints.allowed_methods,
-                            allowed_contracts: allowed_addresses,
-                            max_gas: ext_evm_constraints.max_gas,
-                            max_value: ext_evm_constraints.max_value,
-                            initial_deposit: ext_evm_constraints.initial_deposit,
-                        };
-                        ChainConstraints::EVM(evm_constraints)
-                    }
-                    _ => env::panic_str(&format!(
-                        "Chain ID `{}` expects EVM constraints, found NEAR constraints.",
-                        chain_id_str.clone()
-                    )),
-                }
-            } else {
-                env::panic_str("Invalid chain ID");
-            };
-            constraints_by_chain_id.insert(chain_id, chain_constraints);
+        for chain_id in chain_constraints.keys() {
+            self.assert_chain_enabled(chain_id);
         }
+        let constraints_by_chain_id = parse_chain_constraints(chain_constraints);
 
         let trial_data = TrialData {
             constraints_by_chain_id,
@@ -71,6 +33,10 @@ impl Contract {
             exit_conditions,
             expiration_time,
             creator_account_id: creator_account_id.clone(),
+            balance: NearToken::from_yoctonear(0),
+            paused: false,
+            managers: HashMap::new(),
+            key_allowance,
         };
 
         let initial_storage = env::storage_usage();
@@ -78,15 +44,35 @@ impl Contract {
         self.trial_nonce += 1;
         let trial_id = self.trial_nonce;
 
-        self.trial_data_by_id.insert(trial_id, trial_data);
+        self.trial_data_by_id.insert(trial_id, trial_data.into());
         self.trial_data_by_id.flush();
+        self.trial_stats_by_id.insert(trial_id, TrialStats::default());
+        self.trial_stats_by_id.flush();
+        self.internal_add_trial_to_creator(&creator_account_id, trial_id);
+        self.num_trials += 1;
 
-        self.adjust_deposit(initial_storage, env::storage_usage());
+        // Pay for the trial's storage and the platform fee, and keep the rest as its balance
+        let required_deposit = storage_cost(env::storage_usage() - initial_storage)
+            .checked_add(trial_fee)
+            .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
+        let balance = env::attached_deposit()
+            .checked_sub(required_deposit)
+            .unwrap_or_else(|| {
+                TrialError::InsufficientDeposit {
+                    required: required_deposit.as_yoctonear(),
+                }
+                .panic()
+            });
+        if let Some(trial_data) = self.internal_get_trial_mut(trial_id) {
+            trial_data.balance = balance;
+        }
+        self.internal_collect_fee(trial_fee);
 
-        env::log_str(&format!(
-            "Trial {} created by {}",
-            trial_id, creator_account_id
-        ));
+        TrialEvent::TrialCreated(vec![TrialCreatedData {
+            trial_id,
+            creator_id: creator_account_id,
+        }])
+        .emit();
 
         trial_id
     }
@@ -3,30 +3,56 @@ use crate::*;
 
 #[near]
 impl Contract {
-    /// Deletes a trial. Only callable by the creator.
-    pub fn delete_trial(&mut self, trial_id: TrialId) {
-        let trial_data = self
-            .trial_data_by_id
-            .get(&trial_id)
-            .expect("Trial ID does not exist");
-
-        // Only the creator can delete the trial
-        require!(
-            env::predecessor_account_id() == trial_data.creator_account_id,
-            "Only the trial creator can delete the trial"
-        );
+    /// Deletes a trial. Only callable by the creator or an owner manager.
+    /// Keys are removed in batches of `DELETE_TRIAL_KEYS_BATCH_SIZE`, so this returns
+    /// `false` until every key is gone and must be called again. The trial itself is
+    /// deleted on the call that removes its last key, and its remaining balance is
+    /// refunded to the creator along with the freed storage.
+    pub fn delete_trial(&mut self, trial_id: TrialId) -> bool {
+        let creator_account_id = self
+            .assert_trial_role(trial_id, TrialRole::Owner)
+            .creator_account_id;
 
         let initial_storage = env::storage_usage();
-        self.trial_data_by_id.remove(&trial_id);
+        let (public_keys, is_done) =
+            self.internal_remove_trial_keys_batch(trial_id, DELETE_TRIAL_KEYS_BATCH_SIZE);
+        if !public_keys.is_empty() {
+            TrialEvent::KeysRemoved(vec![KeysRemovedData {
+                trial_id,
+                public_keys,
+                removed_by: env::predecessor_account_id(),
+            }])
+            .emit();
+        }
+
+        // Storage freed by the keys goes back to the trial, and so to the creator
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
         self.trial_data_by_id.flush();
+        if !is_done {
+            return false;
+        }
 
-        self.adjust_deposit(initial_storage, env::storage_usage());
+        let initial_storage = env::storage_usage();
+        let balance = self
+            .internal_remove_trial(trial_id)
+            .map_or(NearToken::from_yoctonear(0), |trial_data| trial_data.balance);
+        self.internal_remove_trial_from_creator(&creator_account_id, trial_id);
+        self.num_trials = self.num_trials.saturating_sub(1);
 
-        env::log_str(&format!(
-            "Trial {} deleted by {}",
-            trial_id,
-            env::predecessor_account_id()
+        let refund = balance.saturating_add(storage_cost(
+            initial_storage.saturating_sub(env::storage_usage()),
         ));
+        if !refund.is_zero() {
+            Promise::new(creator_account_id).transfer(refund);
+        }
+
+        TrialEvent::TrialDeleted(vec![TrialDeletedData {
+            trial_id,
+            deleted_by: env::predecessor_account_id(),
+        }])
+        .emit();
+
+        true
     }
 
     //TODO: add a function to refund unused trials
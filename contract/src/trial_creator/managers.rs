@@ -0,0 +1,65 @@
+// trial_creator/managers.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Grants roles on a trial to another account, replacing any roles it already had.
+    /// Only callable by the creator. Storage is paid from the trial's balance.
+    pub fn add_trial_manager(
+        &mut self,
+        trial_id: TrialId,
+        account_id: AccountId,
+        mut roles: Vec<TrialRole>,
+    ) {
+        self.assert_trial_creator(trial_id);
+
+        roles.sort_by_key(|role| *role as u8);
+        roles.dedup();
+
+        let initial_storage = env::storage_usage();
+        if let Some(trial_data) = self.internal_get_trial_mut(trial_id) {
+            trial_data.managers.insert(account_id.clone(), roles.clone());
+        }
+        self.trial_data_by_id.flush();
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
+
+        TrialEvent::ManagerAdded(vec![TrialManagerData {
+            trial_id,
+            account_id,
+            roles,
+        }])
+        .emit();
+    }
+
+    /// Revokes every role an account holds on a trial. Only callable by the creator.
+    pub fn remove_trial_manager(&mut self, trial_id: TrialId, account_id: AccountId) {
+        self.assert_trial_creator(trial_id);
+
+        let initial_storage = env::storage_usage();
+        let roles = self
+            .internal_get_trial_mut(trial_id)
+            .and_then(|trial_data| trial_data.managers.remove(&account_id))
+            .unwrap_or_default();
+        self.trial_data_by_id.flush();
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
+
+        TrialEvent::ManagerRemoved(vec![TrialManagerData {
+            trial_id,
+            account_id,
+            roles,
+        }])
+        .emit();
+    }
+
+    fn assert_trial_creator(&self, trial_id: TrialId) {
+        assert_not_self_call();
+
+        let trial_data = self
+            .internal_get_trial(trial_id)
+            .unwrap_or_else(|| TrialError::TrialNotFound { trial_id }.panic());
+
+        if env::predecessor_account_id() != trial_data.creator_account_id {
+            TrialError::NotTrialCreator.panic();
+        }
+    }
+}
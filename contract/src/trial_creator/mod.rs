@@ -2,5 +2,12 @@
 //! Module for trial account management, including creation, activation, deletion, and exit.
 
 pub mod add_keys;
+pub mod allowance;
+pub mod balance;
 pub mod create;
 pub mod delete;
+pub mod managers;
+pub mod nonces;
+pub mod remove_keys;
+pub mod status;
+pub mod update;
@@ -0,0 +1,47 @@
+// trial_creator/nonces.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Overrides the last nonce signed for a key on a chain, for when the account's on-chain
+    /// nonce has moved on without the contract, e.g. after a failed broadcast.
+    /// Passing no nonce forgets it, so the next signature may use any nonce.
+    /// Callable by the admin, or the creator or an operator manager of the key's trial.
+    pub fn resync_key_nonce(
+        &mut self,
+        public_key: PublicKey,
+        chain_id: ChainId,
+        last_nonce: Option<U64>,
+    ) {
+        let trial_id = self
+            .internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic())
+            .trial_id;
+        if env::predecessor_account_id() != self.admin_account {
+            self.assert_trial_role(trial_id, TrialRole::Operator);
+        }
+
+        let initial_storage = env::storage_usage();
+        if let Some(key_usage) = self.internal_get_key_usage_mut(&public_key) {
+            match last_nonce {
+                Some(last_nonce) => {
+                    key_usage.last_nonce_by_chain_id.insert(chain_id.clone(), last_nonce.0);
+                }
+                None => {
+                    key_usage.last_nonce_by_chain_id.remove(&chain_id);
+                }
+            }
+        }
+        self.key_usage_by_pk.flush();
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
+
+        TrialEvent::KeyNonceResynced(vec![KeyNonceData {
+            trial_id,
+            public_key,
+            chain_id,
+            last_nonce,
+            updated_by: env::predecessor_account_id(),
+        }])
+        .emit();
+    }
+}
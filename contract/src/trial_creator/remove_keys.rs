@@ -0,0 +1,41 @@
+// trial_creator/remove_keys.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Removes keys from a trial and deletes their access keys.
+    /// Only callable by the creator or a key issuer manager.
+    /// The freed storage is credited back to the trial's balance.
+    pub fn remove_trial_keys(&mut self, trial_id: TrialId, public_keys: Vec<PublicKey>) {
+        self.assert_trial_role(trial_id, TrialRole::KeyIssuer);
+
+        let initial_storage = env::storage_usage();
+
+        for public_key in public_keys.iter() {
+            // Only keys issued for this trial can be removed
+            let belongs_to_trial = self
+                .internal_get_key_usage(public_key)
+                .is_some_and(|key_usage| key_usage.trial_id == trial_id);
+            if !belongs_to_trial {
+                TrialError::AccessDenied.panic();
+            }
+
+            self.internal_remove_key_usage(public_key);
+            Promise::new(env::current_account_id()).delete_key(public_key.clone());
+        }
+
+        self.key_usage_by_pk.flush();
+        self.internal_remove_keys_from_trial(trial_id, &public_keys);
+
+        // Credit the freed storage back to the trial
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
+        self.trial_data_by_id.flush();
+
+        TrialEvent::KeysRemoved(vec![KeysRemovedData {
+            trial_id,
+            public_keys,
+            removed_by: env::predecessor_account_id(),
+        }])
+        .emit();
+    }
+}
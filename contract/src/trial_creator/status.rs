@@ -0,0 +1,76 @@
+// trial_creator/status.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Stops all signing and activations for a trial. Usage data is kept.
+    /// Only callable by the creator or an operator manager.
+    pub fn pause_trial(&mut self, trial_id: TrialId) {
+        self.internal_set_trial_paused(trial_id, true);
+
+        TrialEvent::TrialPaused(vec![TrialStatusData {
+            trial_id,
+            updated_by: env::predecessor_account_id(),
+        }])
+        .emit();
+    }
+
+    /// Lets a paused trial sign again. Only callable by the creator or an operator manager.
+    pub fn resume_trial(&mut self, trial_id: TrialId) {
+        self.internal_set_trial_paused(trial_id, false);
+
+        TrialEvent::TrialResumed(vec![TrialStatusData {
+            trial_id,
+            updated_by: env::predecessor_account_id(),
+        }])
+        .emit();
+    }
+
+    /// Stops a single trial key from signing or activating. Usage data is kept.
+    /// Only callable by the creator or an operator manager of the key's trial.
+    pub fn freeze_key(&mut self, public_key: PublicKey) {
+        let trial_id = self.internal_set_key_frozen(&public_key, true);
+
+        TrialEvent::KeyFrozen(vec![KeyStatusData {
+            trial_id,
+            public_key,
+            updated_by: env::predecessor_account_id(),
+        }])
+        .emit();
+    }
+
+    /// Lets a frozen key sign again.
+    /// Only callable by the creator or an operator manager of the key's trial.
+    pub fn unfreeze_key(&mut self, public_key: PublicKey) {
+        let trial_id = self.internal_set_key_frozen(&public_key, false);
+
+        TrialEvent::KeyUnfrozen(vec![KeyStatusData {
+            trial_id,
+            public_key,
+            updated_by: env::predecessor_account_id(),
+        }])
+        .emit();
+    }
+
+    fn internal_set_trial_paused(&mut self, trial_id: TrialId, paused: bool) {
+        self.assert_trial_role(trial_id, TrialRole::Operator);
+        if let Some(trial_data) = self.internal_get_trial_mut(trial_id) {
+            trial_data.paused = paused;
+        }
+    }
+
+    /// Returns the trial the key belongs to.
+    fn internal_set_key_frozen(&mut self, public_key: &PublicKey, frozen: bool) -> TrialId {
+        let trial_id = self
+            .internal_get_key_usage(public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic())
+            .trial_id;
+        self.assert_trial_role(trial_id, TrialRole::Operator);
+
+        if let Some(key_usage) = self.internal_get_key_usage_mut(public_key) {
+            key_usage.frozen = frozen;
+        }
+
+        trial_id
+    }
+}
@@ -0,0 +1,44 @@
+// trial_creator/update.rs
+use crate::*;
+
+#[near]
+impl Contract {
+    /// Replaces a trial's constraints. Takes the same arguments as `create_trial`.
+    /// Only callable by the creator or an operator manager.
+    /// Storage is paid from the trial's balance.
+    pub fn update_trial_constraints(
+        &mut self,
+        trial_id: TrialId,
+        chain_constraints: HashMap<ChainId, ExtChainConstraints>,
+        usage_constraints: Option<UsageConstraints>,
+        interaction_limits: Option<InteractionLimits>,
+        exit_conditions: Option<ExitConditions>,
+        expiration_time: Option<u64>,
+        key_allowance: Option<NearToken>,
+    ) {
+        self.assert_trial_role(trial_id, TrialRole::Operator);
+
+        for chain_id in chain_constraints.keys() {
+            self.assert_chain_enabled(chain_id);
+        }
+        let constraints_by_chain_id = parse_chain_constraints(chain_constraints);
+
+        let initial_storage = env::storage_usage();
+        if let Some(trial_data) = self.internal_get_trial_mut(trial_id) {
+            trial_data.constraints_by_chain_id = constraints_by_chain_id;
+            trial_data.usage_constraints = usage_constraints;
+            trial_data.interaction_limits = interaction_limits;
+            trial_data.exit_conditions = exit_conditions;
+            trial_data.expiration_time = expiration_time;
+            trial_data.key_allowance = key_allowance;
+        }
+        self.trial_data_by_id.flush();
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
+
+        TrialEvent::TrialConstraintsUpdated(vec![TrialStatusData {
+            trial_id,
+            updated_by: env::predecessor_account_id(),
+        }])
+        .emit();
+    }
+}
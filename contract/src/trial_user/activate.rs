@@ -21,50 +21,106 @@ impl Contract {
     /// Activates a trial by creating a new account with a full access key derived from the trial's key usage data.
     /// Only the trial creator can activate the trial.
     #[payable]
-    pub fn activate_trial(&mut self, new_account_id: String, chain_id: String) {
-        let chain_id = ChainId(chain_id);
+    pub fn activate_trial(&mut self, new_account_id: String, chain_id: ChainId) {
+        self.assert_not_paused();
 
-        let user_account_id: UserAccountId = if chain_id.is_near() {
-            UserAccountId::NEAR(new_account_id.parse().expect("Invalid NEAR account ID"))
-        } else if let Some(_chain_id_num) = chain_id.as_evm_chain_id() {
-            let addr_str = new_account_id.trim_start_matches("0x");
-            let addr_bytes: [u8; 20] = <[u8; 20]>::from_hex(addr_str)
-                .expect("Invalid Ethereum address in allowed_contracts");
-            UserAccountId::EVM(Address::from(addr_bytes))
-        } else {
-            env::panic_str("Invalid chain ID");
+        let user_account_id: UserAccountId = match chain_id {
+            ChainId::Near { .. } => UserAccountId::NEAR(new_account_id.parse().unwrap_or_else(|_| {
+                TrialError::InvalidNearAccountId {
+                    account_id: new_account_id.clone(),
+                }
+                .panic()
+            })),
+            ChainId::Evm(_) => {
+                let addr_str = new_account_id.trim_start_matches("0x");
+                let addr_bytes: [u8; 20] = <[u8; 20]>::from_hex(addr_str).unwrap_or_else(|_| {
+                    TrialError::InvalidEvmAddress {
+                        address: new_account_id.clone(),
+                    }
+                    .panic()
+                });
+                UserAccountId::EVM(Address::from(addr_bytes))
+            }
+            ChainId::Bitcoin { .. } => {
+                // The address is derived from the MPC key, the caller only confirms it
+                let mpc_key = self
+                    .internal_get_key_usage(&env::signer_account_pk())
+                    .unwrap_or_else(|| TrialError::AccessDenied.panic())
+                    .mpc_key;
+                let account = bitcoin_account_from_public_key(
+                    &compress_secp256k1_public_key(&mpc_key),
+                    chain_id.bitcoin_hrp().unwrap(),
+                );
+                if !account.address.eq_ignore_ascii_case(&new_account_id) {
+                    TrialError::BitcoinAddressMismatch {
+                        address: new_account_id,
+                        expected: account.address,
+                    }
+                    .panic();
+                }
+                UserAccountId::Bitcoin(account)
+            }
         };
 
+        let initial_storage = env::storage_usage();
+
         let signer_pk = env::signer_account_pk();
         let key_usage = self
-            .key_usage_by_pk
-            .get_mut(&signer_pk)
-            .expect("No key usage data found for this trial");
+            .internal_get_key_usage_mut(&signer_pk)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
 
-        require!(
-            key_usage
-                .account_id_by_chain_id
-                .insert(chain_id, user_account_id.clone())
-                .is_none(),
-            "The trial has already been activated"
-        );
+        if key_usage.frozen {
+            TrialError::KeyFrozen.panic();
+        }
+        // A key holds a single NEAR account, so it can only be activated on one NEAR network
+        if let Some(activated) = key_usage.account_id_by_chain_id.keys().find(|activated| {
+            **activated == chain_id || (activated.is_near() && chain_id.is_near())
+        }) {
+            TrialError::AlreadyActivated {
+                chain_id: activated.clone(),
+            }
+            .panic();
+        }
+        key_usage
+            .account_id_by_chain_id
+            .insert(chain_id.clone(), user_account_id.clone());
 
         let trial_id = key_usage.trial_id;
-        let trial_data = self
-            .trial_data_by_id
-            .get(&trial_id)
-            .expect("Trial data not found");
 
         // Retrieve the MPC public key for this trial
         let mpc_public_key = key_usage.mpc_key.clone();
 
+        let trial_data = self
+            .internal_get_trial(trial_id)
+            .unwrap_or_else(|| TrialError::TrialNotFound { trial_id }.panic());
+        if trial_data.paused {
+            TrialError::TrialPaused.panic();
+        }
+
+        if let Some(trial_stats) = self.trial_stats_by_id.get_mut(&trial_id) {
+            trial_stats.record_activation(&chain_id);
+        }
+        self.key_usage_by_pk.flush();
+        self.trial_stats_by_id.flush();
+
+        // Storage growth is paid for by the trial
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
+
+        TrialEvent::TrialActivated(vec![TrialActivatedData {
+            trial_id,
+            public_key: signer_pk.clone(),
+            chain_id: chain_id.clone(),
+            account_id: user_account_id.to_string(),
+        }])
+        .emit();
+
         // Ensure the chain constraints are correctly retrieved
         if let (
             UserAccountId::NEAR(ref near_account_id),
             Some(ChainConstraints::NEAR(ref constraints)),
         ) = (
             user_account_id,
-            trial_data.get_chain_constraints(&ChainId("NEAR".to_string())),
+            trial_data.get_chain_constraints(&chain_id),
         ) {
             let account_options = CreateAccountAdvancedOptions {
                 options: CreateAccountOptions {
@@ -73,7 +129,7 @@ impl Contract {
                 new_account_id: near_account_id.clone(),
             };
 
-            let root_account: AccountId = "testnet".parse().unwrap();
+            let root_account = chain_id.near_root_account().unwrap();
             Promise::new(root_account).function_call_weight(
                 "create_account_advanced".to_string(),
                 serde_json::to_vec(&account_options).unwrap(),
@@ -6,16 +6,24 @@ impl Contract {
     /// Allows a trial user to exit the trial, adding a full access key to their account.
     pub fn exit_trial(&mut self, public_key: PublicKey) -> Promise {
         let signer_pk = env::signer_account_pk();
-        let key_usage = self.key_usage_by_pk.get(&signer_pk).expect("Access denied");
+        let key_usage = self
+            .internal_get_key_usage(&signer_pk)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
 
         let trial_data = self
-            .trial_data_by_id
-            .get(&key_usage.trial_id)
-            .expect("Trial data not found");
+            .internal_get_trial(key_usage.trial_id)
+            .unwrap_or_else(|| {
+                TrialError::TrialNotFound {
+                    trial_id: key_usage.trial_id,
+                }
+                .panic()
+            });
 
         // TODO:
-        // Build the transaction to add a full access key
+        // Build the transaction to add a full access key, once `exit_conditions` are met
         // Implement the logic to add a full access key using the MPC contract
+        // Mark the key as exited, count it in `keys_exited` and emit `TrialEvent::Exit`
+        // once the key has been handed over
 
         env::panic_str("Exit trial functionality is not yet implemented");
     }
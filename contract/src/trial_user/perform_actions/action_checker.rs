@@ -2,124 +2,529 @@
 use crate::*;
 use near_sdk::json_types::U128;
 
+/// A single rule violated by an action.
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct ActionViolation {
+    pub code: String,
+    pub message: String,
+}
+
+impl From<&TrialError> for ActionViolation {
+    fn from(error: &TrialError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.message(),
+        }
+    }
+}
+
+/// Result of dry-running an action against a trial's rules.
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct ActionCheckResult {
+    pub allowed: bool,
+    pub violations: Vec<ActionViolation>,
+    /// Interactions left before the transaction limit is hit.
+    pub remaining_interactions: Option<u64>,
+    /// Maximum gas a single action may attach on the action's chain.
+    pub max_gas: Option<U128>,
+    /// Maximum deposit a single action may attach on its chain (yoctoNEAR, wei or satoshis).
+    pub max_deposit: Option<U128>,
+    /// Nanoseconds left until the trial expires.
+    pub time_left: Option<U64>,
+}
+
+impl ActionCheckResult {
+    /// Result for a key that cannot be checked at all.
+    fn rejected(error: TrialError) -> Self {
+        Self {
+            allowed: false,
+            violations: vec![ActionViolation::from(&error)],
+            remaining_interactions: None,
+            max_gas: None,
+            max_deposit: None,
+            time_left: None,
+        }
+    }
+}
+
+/// Collects every rule an action violates, without touching any state.
+pub fn collect_action_violations(
+    trial_data: &TrialData,
+    key_usage: &KeyUsage,
+    action: &Action,
+    current_timestamp: u64,
+) -> Vec<TrialError> {
+    let mut violations = Vec::new();
+
+    // Check the creator hasn't suspended the trial or the key
+    if trial_data.paused {
+        violations.push(TrialError::TrialPaused);
+    }
+    if key_usage.frozen {
+        violations.push(TrialError::KeyFrozen);
+    }
+    if key_usage.exited {
+        violations.push(TrialError::KeyExited);
+    }
+
+    // Check expiration time
+    if trial_data.has_expired(current_timestamp) {
+        violations.push(TrialError::TrialExpired);
+    }
+
+    // Check the transaction limit
+    if !trial_data.is_within_transaction_limit(key_usage.usage_stats.total_interactions) {
+        violations.push(TrialError::TransactionLimitReached);
+    }
+
+    // Check if the chain is enabled for this trial
+    let chain_id = action.chain_id();
+    if trial_data.get_chain_constraints(&chain_id).is_none() {
+        violations.push(TrialError::ChainNotAllowed { chain_id });
+        return violations;
+    }
+
+    // Messages carry no method, gas or deposit, only a recipient and an EIP-712 type.
+    // EIP-191 messages name neither, and must be enabled separately for the chain.
+    if let Action::SignMessage(message_action) = action {
+        if message_action.is_personal_sign() && !trial_data.is_personal_sign_allowed(&chain_id) {
+            violations.push(TrialError::PersonalSignNotAllowed {
+                chain_id: chain_id.clone(),
+            });
+        }
+        if let Some(primary_type) = &message_action.primary_type {
+            if !trial_data.is_primary_type_allowed(primary_type, &chain_id) {
+                violations.push(TrialError::PrimaryTypeNotAllowed {
+                    primary_type: primary_type.clone(),
+                });
+            }
+        }
+        if !message_action.is_personal_sign()
+            && !trial_data
+                .is_message_recipient_allowed(message_action.recipient.as_deref(), &chain_id)
+        {
+            violations.push(TrialError::MessageRecipientNotAllowed {
+                recipient: action.contract(),
+            });
+        }
+        return violations;
+    }
+
+    // Check if the method is allowed. The calls user operations execute only have a selector.
+    let is_method_allowed = match action {
+        Action::EVM(EvmAction {
+            user_operation: Some(_),
+            ..
+        }) => trial_data.is_selector_allowed(action.method_name(), &chain_id),
+        _ => trial_data.is_method_allowed(action.method_name(), &chain_id),
+    };
+    if !is_method_allowed {
+        violations.push(TrialError::MethodNotAllowed {
+            method: action.method_name().to_string(),
+        });
+    }
+
+    // Check if the contract, or every Bitcoin recipient, is allowed
+    let contracts = match action {
+        Action::Bitcoin(bitcoin_action) => bitcoin_action.recipient_scripts.clone(),
+        _ => vec![action.contract()],
+    };
+    for contract in contracts {
+        if !trial_data.is_contract_allowed(&contract, &chain_id) {
+            violations.push(TrialError::ContractNotAllowed { contract });
+        }
+    }
+
+    // Check gas limit
+    let gas = u64::try_from(action.gas()).unwrap_or(u64::MAX);
+    if !trial_data.is_gas_within_limits(gas, &chain_id) {
+        violations.push(TrialError::GasExceedsLimit);
+    }
+
+    // Check deposit limit
+    if !trial_data.is_deposit_within_limits(action.deposit(), &chain_id) {
+        violations.push(TrialError::DepositExceedsLimit);
+    }
+
+    // Check the Bitcoin fee rate
+    if let Action::Bitcoin(bitcoin_action) = action {
+        if !trial_data.is_fee_rate_within_limits(bitcoin_action.fee_rate(), &chain_id) {
+            violations.push(TrialError::FeeRateExceedsLimit);
+        }
+    }
+
+    // Check the EVM fee caps and the key's fee budget
+    if let Action::EVM(evm_action) = action {
+        if !trial_data.are_evm_fees_within_limits(evm_action, &chain_id) {
+            violations.push(TrialError::EvmFeeExceedsLimit);
+        }
+        let fees_used = key_usage
+            .usage_stats
+            .evm_fees_used_by_chain_id
+            .get(&chain_id)
+            .map_or(0, |fees_used| fees_used.0);
+        if !trial_data.is_within_evm_fee_budget(fees_used, evm_action.self_paid_fee(), &chain_id)
+        {
+            violations.push(TrialError::EvmFeeBudgetExceeded);
+        }
+
+        // Check the entry point and who sponsors a user operation
+        if let Some(user_operation) = &evm_action.user_operation {
+            let entry_point = convert_address_to_hex_string(&user_operation.entry_point);
+            if !trial_data.is_entry_point_allowed(&entry_point, &chain_id) {
+                violations.push(TrialError::EntryPointNotAllowed { entry_point });
+            }
+            let paymaster = user_operation
+                .paymaster
+                .as_ref()
+                .map(convert_address_to_hex_string);
+            if !trial_data.is_paymaster_allowed(paymaster.as_deref(), &chain_id) {
+                violations.push(match paymaster {
+                    Some(paymaster) => TrialError::PaymasterNotAllowed { paymaster },
+                    None => TrialError::PaymasterRequired,
+                });
+            }
+        }
+    }
+
+    violations
+}
+
+/// Records an allowed action in the key's usage statistics.
+pub fn record_action_usage(usage_stats: &mut UsageStats, action: &Action, day: u64) {
+    usage_stats.total_interactions += 1;
+    prune_day_buckets(&mut usage_stats.interactions_per_day, day);
+    *usage_stats.interactions_per_day.entry(day).or_insert(0) += 1;
+    *usage_stats
+        .methods_called
+        .entry(action.method_name().to_string())
+        .or_insert(0) += 1;
+    *usage_stats
+        .contracts_called
+        .entry(action.contract())
+        .or_insert(0) += 1;
+    usage_stats.gas_used = usage_stats
+        .gas_used
+        .checked_add(action.gas())
+        .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
+
+    usage_stats.deposit_used = U128(
+        usage_stats
+            .deposit_used
+            .0
+            .checked_add(action.deposit())
+            .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic()),
+    );
+
+    if let Action::EVM(evm_action) = action {
+        let fees_used = usage_stats
+            .evm_fees_used_by_chain_id
+            .entry(action.chain_id())
+            .or_insert(U128(0));
+        fees_used.0 = fees_used
+            .0
+            .checked_add(evm_action.self_paid_fee())
+            .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
+    }
+}
+
 #[near]
 impl Contract {
+    /// Dry-runs an action for a trial key and reports every rule it would violate.
+    /// Does not modify any usage data.
+    pub fn check_action(&self, public_key: PublicKey, action: Action) -> ActionCheckResult {
+        let key_usage = match self.internal_get_key_usage(&public_key) {
+            Some(key_usage) => key_usage,
+            None => return ActionCheckResult::rejected(TrialError::AccessDenied),
+        };
+        let trial_data = match self.internal_get_trial(key_usage.trial_id) {
+            Some(trial_data) => trial_data,
+            None => {
+                return ActionCheckResult::rejected(TrialError::TrialNotFound {
+                    trial_id: key_usage.trial_id,
+                })
+            }
+        };
+
+        let current_timestamp = env::block_timestamp();
+        let mut violations = collect_action_violations(
+            &trial_data,
+            &key_usage,
+            &action,
+            current_timestamp,
+        );
+        violations.extend(self.collect_chain_violations(&action));
+        if self.paused {
+            violations.insert(0, TrialError::ContractPaused);
+        }
+
+        let chain_id = action.chain_id();
+        let (max_gas, max_deposit) = match trial_data.get_chain_constraints(&chain_id) {
+            Some(ChainConstraints::NEAR(constraints)) => (
+                constraints.max_gas.map(|gas| U128(gas.as_gas() as u128)),
+                constraints.max_deposit.map(|deposit| U128(deposit.as_yoctonear())),
+            ),
+            Some(ChainConstraints::EVM(constraints)) => (
+                constraints.max_gas.map(|gas| U128(gas as u128)),
+                constraints.max_value,
+            ),
+            Some(ChainConstraints::Bitcoin(constraints)) => (
+                None,
+                constraints.max_amount.map(|amount| U128(amount.0 as u128)),
+            ),
+            None => (None, None),
+        };
+
+        ActionCheckResult {
+            allowed: violations.is_empty(),
+            violations: violations.iter().map(ActionViolation::from).collect(),
+            remaining_interactions: trial_data
+                .exit_conditions
+                .as_ref()
+                .and_then(|exit_conditions| exit_conditions.transaction_limit)
+                .map(|limit| limit.saturating_sub(key_usage.usage_stats.total_interactions)),
+            max_gas,
+            max_deposit,
+            time_left: trial_data
+                .expiration_time
+                .map(|expiration| U64(expiration.saturating_sub(current_timestamp))),
+        }
+    }
+
+    /// Checks an action signed by the current trial key and records it in the key's usage.
+    /// Panics with the first violated rule.
     pub(crate) fn assert_action_allowed(&mut self, action: &Action) -> (TrialData, KeyUsage) {
+        self.assert_not_paused();
+
         let public_key = env::signer_account_pk();
 
         // Fetch KeyUsage
         let mut key_usage = self
-            .key_usage_by_pk
-            .get(&public_key)
-            .expect("Access denied")
-            .clone();
+            .internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
 
         // Fetch TrialData
         let trial_data = self
-            .trial_data_by_id
-            .get(&key_usage.trial_id)
-            .cloned()
-            .expect("Trial data not found");
+            .internal_get_trial(key_usage.trial_id)
+            .unwrap_or_else(|| {
+                TrialError::TrialNotFound {
+                    trial_id: key_usage.trial_id,
+                }
+                .panic()
+            });
 
-        // Check expiration time
-        if trial_data.has_expired(env::block_timestamp()) {
-            env::panic_str("Trial period has expired");
+        let current_timestamp = env::block_timestamp();
+        let mut violations =
+            collect_action_violations(&trial_data, &key_usage, action, current_timestamp);
+        violations.extend(self.collect_chain_violations(action));
+        if let Some(violation) = violations.first() {
+            violation.panic();
         }
 
-        // Now check action-specific constraints
-        match action {
-            Action::NEAR(near_action) => {
-                let chain_id = ChainId("NEAR".to_string());
+        let initial_storage = env::storage_usage();
 
-                // Check if the method is allowed
-                if !trial_data.is_method_allowed(&near_action.method_name, &chain_id) {
-                    env::panic_str("Method not allowed");
-                }
+        // Update usage statistics
+        let day = day_index(current_timestamp);
+        let is_new_active_key = !key_usage.usage_stats.interactions_per_day.contains_key(&day);
+        record_action_usage(&mut key_usage.usage_stats, action, day);
 
-                // Check if the contract is allowed
-                if !trial_data.is_contract_allowed(near_action.contract_id.as_str(), &chain_id) {
-                    env::panic_str("Contract not allowed");
-                }
+        // Update the trial's aggregates
+        if let Some(trial_stats) = self.trial_stats_by_id.get_mut(&key_usage.trial_id) {
+            trial_stats.record_action(action, day, is_new_active_key);
+        }
 
-                // Check gas limit
-                if !trial_data.is_gas_within_limits(near_action.gas_attached.as_gas(), &chain_id) {
-                    env::panic_str("Attached gas exceeds maximum allowed");
-                }
+        // Update key usage in storage
+        if let Some(stored_key_usage) = self.internal_get_key_usage_mut(&public_key) {
+            *stored_key_usage = key_usage.clone();
+        }
+        self.key_usage_by_pk.flush();
+        self.trial_stats_by_id.flush();
 
-                // Check deposit limit
-                if !trial_data.is_deposit_within_limits(
-                    near_action.deposit_attached.as_yoctonear(),
-                    &chain_id,
-                ) {
-                    env::panic_str("Attached deposit exceeds maximum allowed");
-                }
+        // Storage growth is paid for by the trial
+        self.charge_trial_storage(key_usage.trial_id, initial_storage, env::storage_usage());
 
-                // Update usage statistics
-                key_usage.usage_stats.total_interactions += 1;
-                key_usage.usage_stats.gas_used = key_usage
-                    .usage_stats
-                    .gas_used
-                    .checked_add(near_action.gas_attached.as_gas() as u128)
-                    .expect("Gas overflow");
-
-                key_usage.usage_stats.deposit_used = U128(
-                    key_usage
-                        .usage_stats
-                        .deposit_used
-                        .0
-                        .checked_add(near_action.deposit_attached.as_yoctonear())
-                        .expect("Deposit overflow"),
-                );
-            }
-            Action::EVM(evm_action) => {
-                let chain_id = ChainId(evm_action.chain_id.to_string());
+        (trial_data, key_usage)
+    }
 
-                // Check if the method is allowed
-                if !trial_data.is_method_allowed(&evm_action.method_name, &chain_id) {
-                    env::panic_str("Method not allowed");
-                }
+    /// Records the nonce and hash of a payload the current trial key is about to sign,
+    /// rejecting replays. Storage growth is paid for by the trial.
+    pub(crate) fn internal_record_signed_payload(
+        &mut self,
+        chain_id: &ChainId,
+        nonce: Option<u64>,
+        payload_hash: CryptoHash,
+    ) {
+        let initial_storage = env::storage_usage();
 
-                // Convert contract address to hex string
-                let contract_address_hex =
-                    format!("0x{}", hex::encode(evm_action.contract_address));
+        let key_usage = self
+            .internal_get_key_usage_mut(&env::signer_account_pk())
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+        key_usage.record_signed_payload(chain_id, nonce, payload_hash);
+        let trial_id = key_usage.trial_id;
+        self.key_usage_by_pk.flush();
 
-                // Check if the contract is allowed
-                if !trial_data.is_contract_allowed(&contract_address_hex, &chain_id) {
-                    env::panic_str("Contract not allowed");
-                }
+        self.charge_trial_storage(trial_id, initial_storage, env::storage_usage());
+    }
+}
 
-                // Check gas limit
-                if !trial_data.is_gas_within_limits(evm_action.gas_limit as u64, &chain_id) {
-                    env::panic_str("Gas limit exceeds maximum allowed");
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
 
-                // Check value limit
-                if !trial_data.is_deposit_within_limits(evm_action.value.0, &chain_id) {
-                    env::panic_str("Value exceeds maximum allowed");
-                }
+    const CONTRACT_ADDRESS: &str = "0x5fbdb2315678afecb367f032d93f642f64180aa3";
 
-                // Update usage statistics
-                key_usage.usage_stats.total_interactions += 1;
-                key_usage.usage_stats.gas_used = key_usage
-                    .usage_stats
-                    .gas_used
-                    .checked_add(evm_action.gas_limit)
-                    .expect("Gas overflow");
-
-                key_usage.usage_stats.deposit_used = U128(
-                    key_usage
-                        .usage_stats
-                        .deposit_used
-                        .0
-                        .checked_add(evm_action.value.0)
-                        .expect("Value overflow"),
-                );
-            }
-        }
+    /// A contract with one EVM trial on chain 1 and a key for it.
+    fn setup(initial_deposit: u128) -> (Contract, PublicKey) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .build());
 
-        // Update key usage in storage
-        self.key_usage_by_pk.insert(public_key, key_usage.clone());
+        let mut contract = Contract::new(accounts(0), accounts(2));
+        let public_key: PublicKey = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+            .parse()
+            .unwrap();
+        let constraints = EvmConstraints {
+            allowed_methods: vec![MethodPattern::Exact("transfer".to_string())],
+            allowed_contracts: vec![parse_evm_address(CONTRACT_ADDRESS)],
+            allowed_selectors: Vec::new(),
+            max_gas: None,
+            max_value: None,
+            initial_deposit: U128(initial_deposit),
+            max_fee_per_gas_cap: None,
+            max_priority_fee_cap: None,
+            allow_personal_sign: false,
+            allowed_verifying_contracts: None,
+            allowed_primary_types: None,
+            allowed_paymasters: None,
+            allowed_entry_points: None,
+        };
+        let trial_data = TrialData {
+            constraints_by_chain_id: HashMap::from([(
+                ChainId::Evm(1),
+                ChainConstraints::EVM(constraints),
+            )]),
+            usage_constraints: None,
+            interaction_limits: None,
+            exit_conditions: None,
+            expiration_time: None,
+            creator_account_id: accounts(1),
+            balance: NearToken::from_near(1),
+            paused: false,
+            managers: HashMap::new(),
+            key_allowance: None,
+        };
+        let key_usage = KeyUsage {
+            trial_id: 1,
+            mpc_key: public_key.clone(),
+            account_id_by_chain_id: HashMap::new(),
+            usage_stats: UsageStats::default(),
+            frozen: false,
+            exited: false,
+            allowance: NearToken::from_near(1),
+            last_nonce_by_chain_id: HashMap::new(),
+            recent_payload_hashes: Vec::new(),
+        };
+        contract.trial_data_by_id.insert(1, trial_data.into());
+        contract
+            .key_usage_by_pk
+            .insert(public_key.clone(), key_usage.into());
+        contract.chain_registry.insert(
+            ChainId::Evm(1),
+            ChainMetadata {
+                display_name: "Ethereum".to_string(),
+                native_decimals: 18,
+                default_gas_limit: None,
+                max_gas_limit: None,
+                max_fee_per_gas: Some(U128(100)),
+                max_priority_fee_per_gas: None,
+                supports_eip1559: false,
+                enabled: true,
+            },
+        );
+
+        (contract, public_key)
+    }
+
+    fn evm_action(
+        transaction_type: Option<EvmTransactionType>,
+        gas_limit: u128,
+        max_fee_per_gas: u128,
+    ) -> Action {
+        Action::EVM(EvmAction {
+            chain_id: 1,
+            method_name: "transfer".to_string(),
+            contract_address: parse_evm_address(CONTRACT_ADDRESS),
+            gas_limit,
+            value: U128(0),
+            max_fee_per_gas: U128(max_fee_per_gas),
+            max_priority_fee_per_gas: None,
+            transaction_type,
+            user_operation: None,
+        })
+    }
+
+    fn violation_codes(result: &ActionCheckResult) -> Vec<&str> {
+        result
+            .violations
+            .iter()
+            .map(|violation| violation.code.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn check_action_applies_the_chain_fee_rules() {
+        let (contract, public_key) = setup(u128::MAX);
+
+        let result = contract.check_action(
+            public_key.clone(),
+            evm_action(Some(EvmTransactionType::Legacy), 21_000, 100),
+        );
+        assert!(result.allowed);
+
+        let result = contract.check_action(
+            public_key,
+            evm_action(Some(EvmTransactionType::Eip1559), 21_000, 101),
+        );
+        assert!(!result.allowed);
+        assert_eq!(
+            violation_codes(&result),
+            ["EIP1559_NOT_SUPPORTED", "FEE_EXCEEDS_CHAIN_CAP"]
+        );
+    }
+    #[test]
+    fn check_action_applies_the_trial_fee_cap_and_budget() {
+        // Exactly enough for one 21,000 gas transaction at 100 wei per gas
+        let (mut contract, public_key) = setup(2_100_000);
+        let action = evm_action(Some(EvmTransactionType::Legacy), 21_000, 100);
+
+        let result = contract.check_action(public_key.clone(), action.clone());
+        assert!(result.allowed);
+
+        contract
+            .internal_get_key_usage_mut(&public_key)
+            .unwrap()
+            .usage_stats
+            .evm_fees_used_by_chain_id
+            .insert(ChainId::Evm(1), U128(1));
+        if let Some(ChainConstraints::EVM(constraints)) = contract
+            .internal_get_trial_mut(1)
+            .unwrap()
+            .constraints_by_chain_id
+            .get_mut(&ChainId::Evm(1))
+        {
+            constraints.max_fee_per_gas_cap = Some(U128(99));
+        }
 
-        (trial_data.clone(), key_usage)
+        let result = contract.check_action(public_key, action);
+        assert!(!result.allowed);
+        assert_eq!(
+            violation_codes(&result),
+            ["EVM_FEE_EXCEEDS_LIMIT", "EVM_FEE_BUDGET_EXCEEDED"]
+        );
     }
 }
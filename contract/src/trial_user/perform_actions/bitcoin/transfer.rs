@@ -0,0 +1,373 @@
+// trial_user/perform_actions/bitcoin/transfer.rs
+use crate::*;
+use hex::FromHex;
+use omni_transaction::bitcoin::bitcoin_transaction::BitcoinTransaction;
+use omni_transaction::bitcoin::types::{
+    Amount, EcdsaSighashType, Hash, LockTime, OutPoint, ScriptBuf, Sequence, TxIn, TxOut, Txid,
+    Version, Witness,
+};
+use omni_transaction::types::BITCOIN;
+
+/// An unspent output of the trial account, spent as a transaction input.
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct BitcoinInput {
+    pub txid: String, // Hex, in the byte order block explorers display
+    pub vout: u32,
+    pub value: U64, // Satoshis
+}
+
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct BitcoinOutput {
+    pub script_pubkey: String, // Hex
+    pub value: U64,            // Satoshis
+}
+
+/// Adds up satoshi amounts, panicking on overflow.
+fn sum_satoshis(mut values: impl Iterator<Item = u64>) -> u64 {
+    values
+        .try_fold(0u64, u64::checked_add)
+        .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic())
+}
+
+/// Estimates the virtual size of a transaction spending P2WPKH inputs.
+pub fn estimate_p2wpkh_vsize(num_inputs: usize, outputs: &[BitcoinOutput]) -> u64 {
+    // Version, lock time, input and output counts, and the segwit marker and flag
+    let overhead = 11;
+    // Outpoint, empty script sig and sequence, plus the discounted signature and public key
+    let inputs = 68 * num_inputs as u64;
+    // Value and script length prefix, plus the script itself
+    let outputs: u64 = outputs
+        .iter()
+        .map(|output| 9 + output.script_pubkey.len() as u64 / 2)
+        .sum();
+    overhead + inputs + outputs
+}
+
+/// Builds the unsigned version 2 transaction.
+pub fn build_bitcoin_transaction(
+    inputs: &[BitcoinInput],
+    outputs: &[BitcoinOutput],
+) -> BitcoinTransaction {
+    let tx_inputs = inputs
+        .iter()
+        .map(|input| {
+            // Transaction IDs are displayed with their bytes reversed
+            let mut txid = <[u8; 32]>::from_hex(&input.txid).unwrap_or_else(|_| {
+                TrialError::InvalidBitcoinTransaction {
+                    reason: "input txid must be 32 hex encoded bytes",
+                }
+                .panic()
+            });
+            txid.reverse();
+
+            TxIn {
+                previous_output: OutPoint {
+                    txid: Txid(Hash(txid)),
+                    vout: input.vout,
+                },
+                script_sig: ScriptBuf::default(),
+                sequence: Sequence::MAX,
+                witness: Witness::default(),
+            }
+        })
+        .collect();
+
+    let tx_outputs = outputs
+        .iter()
+        .map(|output| TxOut {
+            value: Amount::from_sat(output.value.0),
+            script_pubkey: ScriptBuf(hex::decode(&output.script_pubkey).unwrap_or_else(|_| {
+                TrialError::InvalidBitcoinScript {
+                    script: output.script_pubkey.clone(),
+                }
+                .panic()
+            })),
+        })
+        .collect();
+
+    TransactionBuilder::new::<BITCOIN>()
+        .version(Version::Two)
+        .inputs(tx_inputs)
+        .outputs(tx_outputs)
+        .lock_time(LockTime::from_height(0).unwrap())
+        .build()
+}
+
+/// Builds the BIP-143 preimage of every input. Their double SHA-256 hashes are the
+/// sighashes the MPC is asked to sign.
+pub fn bitcoin_sighash_preimages(
+    transaction: &BitcoinTransaction,
+    inputs: &[BitcoinInput],
+    script_code: &[u8],
+) -> Vec<Vec<u8>> {
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            transaction.build_for_signing_segwit(
+                EcdsaSighashType::All,
+                index,
+                &ScriptBuf(script_code.to_vec()),
+                input.value.0,
+            )
+        })
+        .collect()
+}
+
+/// Identifies a transfer by the UTXOs it spends, in any order. A UTXO can only be spent
+/// once, so spending the same outpoints again is a replay, however the inputs are ordered.
+pub fn bitcoin_outpoints_hash(inputs: &[BitcoinInput]) -> CryptoHash {
+    let mut outpoints: Vec<String> = inputs
+        .iter()
+        .map(|input| format!("{}:{}", input.txid.to_lowercase(), input.vout))
+        .collect();
+    outpoints.sort();
+    env::sha256_array(outpoints.join(",").as_bytes())
+}
+
+/// Validates a transfer from `account` and builds the action it is checked as, along with
+/// the preimage of every input. Outputs paying back to the account are treated as change.
+pub fn build_bitcoin_transfer(
+    account: &BitcoinAccount,
+    chain_id: ChainId,
+    inputs: &[BitcoinInput],
+    outputs: &[BitcoinOutput],
+) -> (Action, Vec<Vec<u8>>) {
+    if inputs.is_empty() || outputs.is_empty() {
+        TrialError::InvalidBitcoinTransaction {
+            reason: "at least one input and one output are required",
+        }
+        .panic();
+    }
+    if inputs.len() > MAX_BITCOIN_INPUTS {
+        TrialError::InvalidBitcoinTransaction {
+            reason: "too many inputs",
+        }
+        .panic();
+    }
+
+    let change_script = hex::encode(account.script_pubkey());
+    let total_in = sum_satoshis(inputs.iter().map(|input| input.value.0));
+    let total_out = sum_satoshis(outputs.iter().map(|output| output.value.0));
+    let fee = total_in.checked_sub(total_out).unwrap_or_else(|| {
+        TrialError::InvalidBitcoinTransaction {
+            reason: "outputs exceed inputs",
+        }
+        .panic()
+    });
+
+    let recipients: Vec<&BitcoinOutput> = outputs
+        .iter()
+        .filter(|output| !output.script_pubkey.eq_ignore_ascii_case(&change_script))
+        .collect();
+    let action = Action::Bitcoin(BitcoinAction {
+        chain_id,
+        recipient_scripts: recipients
+            .iter()
+            .map(|output| output.script_pubkey.to_lowercase())
+            .collect(),
+        amount: sum_satoshis(recipients.iter().map(|output| output.value.0)),
+        fee,
+        vsize: estimate_p2wpkh_vsize(inputs.len(), outputs),
+    });
+
+    let transaction = build_bitcoin_transaction(inputs, outputs);
+    let preimages = bitcoin_sighash_preimages(&transaction, inputs, &account.script_code());
+    (action, preimages)
+}
+
+#[near]
+impl Contract {
+    /// Signs a P2WPKH transaction spending the trial account's UTXOs.
+    /// Outputs paying back to the trial account are treated as change; every other output
+    /// must pay an allowed recipient. One MPC signature is requested per input, and the
+    /// signatures are returned in input order.
+    pub fn call_bitcoin_transfer(
+        &mut self,
+        chain_id: ChainId,
+        inputs: Vec<BitcoinInput>,
+        outputs: Vec<BitcoinOutput>,
+    ) -> Promise {
+        if !chain_id.is_bitcoin() {
+            TrialError::InvalidChainId {
+                chain_id: chain_id.to_string(),
+            }
+            .panic();
+        }
+
+        let signer_pk = env::signer_account_pk();
+        let account = self
+            .internal_get_key_usage(&signer_pk)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic())
+            .bitcoin_account(&chain_id);
+        let (action, preimages) = build_bitcoin_transfer(&account, chain_id, &inputs, &outputs);
+
+        let (_, key_usage) = self.assert_action_allowed(&action);
+
+        let sighashes: Vec<[u8; 32]> = preimages
+            .iter()
+            .map(|preimage| double_sha256(preimage))
+            .collect();
+        // Bitcoin has no account nonce, so the spent outpoints identify the transaction
+        let payload_hash = bitcoin_outpoints_hash(&inputs);
+        self.internal_record_signed_payload(&action.chain_id(), None, payload_hash);
+
+        self.internal_sign_payloads(key_usage.trial_id, &action, &sighashes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Native P2WPKH example from BIP-143. The first input is a P2PK output, and the second
+    // is the P2WPKH output whose sighash the spec computes.
+    const BIP143_TXIDS: [&str; 2] = [
+        "9f96ade4b41d5433f4eda31e1738ec2b36f6e7d1420d94a6af99801a88f7f7ff",
+        "8ac60eb9575db5b2d987e29f301b5b819ea83a5c6579d282d189cc04b8e151ef",
+    ];
+    const BIP143_PUBKEY_HASH: &str = "1d0f172a0ecb48aee1be1f2687d2963ae33f71a1";
+
+    fn bip143_inputs() -> Vec<BitcoinInput> {
+        vec![
+            BitcoinInput {
+                txid: BIP143_TXIDS[0].to_string(),
+                vout: 0,
+                value: U64(625_000_000),
+            },
+            BitcoinInput {
+                txid: BIP143_TXIDS[1].to_string(),
+                vout: 1,
+                value: U64(600_000_000),
+            },
+        ]
+    }
+
+    fn bip143_outputs() -> Vec<BitcoinOutput> {
+        vec![
+            BitcoinOutput {
+                script_pubkey: "76a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac".to_string(),
+                value: U64(112_340_000),
+            },
+            BitcoinOutput {
+                script_pubkey: "76a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac".to_string(),
+                value: U64(223_450_000),
+            },
+        ]
+    }
+
+    fn bip143_account() -> BitcoinAccount {
+        BitcoinAccount {
+            address: "bc1qr583w2swedy2acd7rung055k8t3n7udp7vyzyg".to_string(),
+            pubkey_hash: <[u8; 20]>::from_hex(BIP143_PUBKEY_HASH).unwrap(),
+        }
+    }
+
+    #[test]
+    fn sighash_preimage_matches_bip143_native_p2wpkh_example() {
+        let inputs = bip143_inputs();
+        // The example is a version 1 transaction with a lock time and a non-final sequence
+        let tx_inputs = inputs
+            .iter()
+            .zip([Sequence(0xffffffee), Sequence::MAX])
+            .map(|(input, sequence)| {
+                let mut txid = <[u8; 32]>::from_hex(&input.txid).unwrap();
+                txid.reverse();
+                TxIn {
+                    previous_output: OutPoint {
+                        txid: Txid(Hash(txid)),
+                        vout: input.vout,
+                    },
+                    script_sig: ScriptBuf::default(),
+                    sequence,
+                    witness: Witness::default(),
+                }
+            })
+            .collect();
+        let tx_outputs = bip143_outputs()
+            .iter()
+            .map(|output| TxOut {
+                value: Amount::from_sat(output.value.0),
+                script_pubkey: ScriptBuf(hex::decode(&output.script_pubkey).unwrap()),
+            })
+            .collect();
+        let transaction: BitcoinTransaction = TransactionBuilder::new::<BITCOIN>()
+            .version(Version::One)
+            .inputs(tx_inputs)
+            .outputs(tx_outputs)
+            .lock_time(LockTime::from_height(17).unwrap())
+            .build();
+
+        let preimages =
+            bitcoin_sighash_preimages(&transaction, &inputs, &bip143_account().script_code());
+
+        assert_eq!(
+            hex::encode(&preimages[1]),
+            "0100000096b827c8483d4e9b96712b6713a7b68d6e8003a781feba36c31143470b4efd37\
+             52b0a642eea2fb7ae638c36f6252b6750293dbe574a806984b8e4d8548339a3b\
+             ef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a01000000\
+             1976a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac0046c32300000000ffffffff\
+             863ef3e1a92afbfdb97f31ad0fc7683ee943e9abcf2501590ff8f6551f47e5e5\
+             1100000001000000"
+        );
+        assert_eq!(
+            hex::encode(double_sha256(&preimages[1])),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+    }
+
+    #[test]
+    fn transfer_signs_the_bip143_example_as_a_final_version_2_transaction() {
+        let chain_id: ChainId = "BITCOIN".parse().unwrap();
+        let (action, preimages) = build_bitcoin_transfer(
+            &bip143_account(),
+            chain_id,
+            &bip143_inputs(),
+            &bip143_outputs(),
+        );
+
+        let Action::Bitcoin(action) = action else {
+            panic!("expected a Bitcoin action");
+        };
+        assert_eq!(action.amount, 335_790_000);
+        assert_eq!(action.fee, 889_210_000);
+        assert_eq!(action.recipient_scripts.len(), 2);
+
+        // Same as the spec's preimage, apart from the version, sequences and lock time
+        assert_eq!(
+            hex::encode(&preimages[1]),
+            "0200000096b827c8483d4e9b96712b6713a7b68d6e8003a781feba36c31143470b4efd37\
+             752adad0a7b9ceca853768aebb6965eca126a62965f698a0c1bc43d83db632ad\
+             ef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a01000000\
+             1976a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac0046c32300000000ffffffff\
+             863ef3e1a92afbfdb97f31ad0fc7683ee943e9abcf2501590ff8f6551f47e5e5\
+             0000000001000000"
+        );
+    }
+
+    fn input(txid_byte: char, vout: u32) -> BitcoinInput {
+        BitcoinInput {
+            txid: txid_byte.to_string().repeat(64),
+            vout,
+            value: U64(1_000),
+        }
+    }
+
+    #[test]
+    fn outpoints_hash_ignores_input_order() {
+        let inputs = [input('a', 0), input('B', 1)];
+        let reordered = [input('b', 1), input('A', 0)];
+        assert_eq!(
+            bitcoin_outpoints_hash(&inputs),
+            bitcoin_outpoints_hash(&reordered)
+        );
+
+        let other_vout = [input('a', 0), input('b', 2)];
+        assert_ne!(
+            bitcoin_outpoints_hash(&inputs),
+            bitcoin_outpoints_hash(&other_vout)
+        );
+    }
+}
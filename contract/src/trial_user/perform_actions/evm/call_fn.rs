@@ -1,15 +1,88 @@
 use crate::perform_actions::serialization::{SerializableParam, SerializableToken};
 use crate::*;
-use env::keccak256;
 use ethabi::{Function, Param, StateMutability, Token};
-use hex::FromHex;
 use near_sdk::json_types::U128;
-use near_sdk::PromiseError;
-use omni_transaction::evm::evm_transaction::EVMTransaction;
 use omni_transaction::evm::evm_transaction_builder::EVMTransactionBuilder;
-use omni_transaction::evm::types::{AccessList, Address, Signature};
+use omni_transaction::evm::types::{AccessList, Address};
 use omni_transaction::transaction_builder::TxBuilder;
 
+/// ABI encodes a function call from its name, parameter types and arguments.
+pub fn encode_function_call(
+    method_name: String,
+    method_params: Vec<SerializableParam>,
+    args: Vec<SerializableToken>,
+) -> Vec<u8> {
+    // Convert SerializableParamType to ethabi::ParamType
+    let ethabi_params: Vec<Param> = method_params.into_iter().map(|p| p.into()).collect();
+    // Convert SerializableToken to ethabi::Token
+    let ethabi_args: Vec<Token> = args.into_iter().map(|t| t.into()).collect();
+
+    // Build the function object
+    #[allow(deprecated)]
+    let function = Function {
+        name: method_name,
+        inputs: ethabi_params,
+        outputs: vec![], // Adjust if needed
+        constant: Some(false),
+        state_mutability: StateMutability::NonPayable,
+    };
+
+    // Encode the function call data
+    function
+        .encode_input(&ethabi_args)
+        .unwrap_or_else(|_| TrialError::AbiEncodingFailed.panic())
+}
+
+/// Builds the transaction and returns the bytes the MPC is asked to sign.
+/// Legacy and EIP-2930 transactions are priced with `max_fee_per_gas` as the gas price.
+#[allow(clippy::too_many_arguments)]
+pub fn build_evm_transaction(
+    transaction_type: EvmTransactionType,
+    chain_id: u64,
+    contract_address: Address,
+    input_data: Vec<u8>,
+    gas_limit: U128,
+    value: U128,
+    nonce: U64,
+    max_fee_per_gas: U128,
+    max_priority_fee_per_gas: Option<U128>,
+    access_list: AccessList,
+) -> Vec<u8> {
+    match transaction_type {
+        EvmTransactionType::Legacy => build_legacy_evm_transaction(
+            chain_id,
+            contract_address,
+            input_data,
+            gas_limit,
+            value,
+            nonce,
+            max_fee_per_gas,
+        ),
+        EvmTransactionType::AccessList => build_access_list_evm_transaction(
+            chain_id,
+            contract_address,
+            input_data,
+            gas_limit,
+            value,
+            nonce,
+            max_fee_per_gas,
+            &access_list,
+        ),
+        EvmTransactionType::Eip1559 => EVMTransactionBuilder::new()
+            .chain_id(chain_id)
+            .nonce(nonce.0)
+            .max_priority_fee_per_gas(max_priority_fee_per_gas.map_or(0, |fee| fee.0))
+            .max_fee_per_gas(max_fee_per_gas.0)
+            .gas_limit(gas_limit.0)
+            .to(contract_address)
+            .value(value.0)
+            .input(input_data)
+            .access_list(access_list)
+            .build()
+            .build_for_signing(),
+    }
+}
+
 #[near]
 impl Contract {
     pub fn call_evm_contract(
@@ -19,125 +92,62 @@ impl Contract {
         method_name: String,
         method_params: Vec<SerializableParam>,
         args: Vec<SerializableToken>,
-        gas_limit: U128,
+        gas_limit: Option<U128>,
         value: U128,
         nonce: U64,
-        max_fee_per_gas: U128,
-        max_priority_fee_per_gas: U128,
+        transaction_type: Option<EvmTransactionType>,
+        gas_price: Option<U128>,
+        max_fee_per_gas: Option<U128>,
+        max_priority_fee_per_gas: Option<U128>,
         access_list: AccessList,
     ) -> Promise {
         // Parse the contract address
-        let addr_str = contract_address.trim_start_matches("0x");
-        let addr_bytes: [u8; 20] =
-            <[u8; 20]>::from_hex(addr_str).expect("Invalid Ethereum address in allowed_contracts");
-        let contract_address = Address::from(addr_bytes);
+        let contract_address = parse_evm_address(&contract_address);
 
+        // Apply the chain registry's defaults. Its fee caps are checked with the action
+        let registry_chain_id = ChainId::Evm(chain_id);
+        let gas_limit = self.internal_gas_limit(&registry_chain_id, gas_limit);
+        let transaction_type =
+            self.internal_evm_transaction_type(&registry_chain_id, transaction_type);
+        let (max_fee_per_gas, max_priority_fee_per_gas) = evm_fee_params(
+            transaction_type,
+            gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        );
         let action = Action::EVM(EvmAction {
             chain_id,
             method_name: method_name.clone(),
             contract_address,
             gas_limit: gas_limit.0,
             value,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            transaction_type: Some(transaction_type),
+            user_operation: None,
         });
 
-        let (trial_data, key_usage) = self.assert_action_allowed(&action);
-
-        // Check exit conditions if any
-        if let Some(exit_conditions) = &trial_data.exit_conditions {
-            // Check transaction limit
-            if let Some(transaction_limit) = exit_conditions.transaction_limit {
-                if key_usage.usage_stats.total_interactions > transaction_limit {
-                    env::panic_str("Transaction limit reached");
-                }
-            }
-            // Additional exit conditions can be checked here
-        }
-
-        // Convert SerializableParamType to ethabi::ParamType
-        let ethabi_params: Vec<Param> = method_params.into_iter().map(|p| p.into()).collect();
-        // Convert SerializableToken to ethabi::Token
-        let ethabi_args: Vec<Token> = args.into_iter().map(|t| t.into()).collect();
-
-        // Build the function object
-        let function = Function {
-            name: method_name.clone(),
-            inputs: ethabi_params.clone(),
-            outputs: vec![], // Adjust if needed
-            constant: Some(false),
-            state_mutability: StateMutability::NonPayable,
-        };
-
-        // Encode the function call data
-        let input_data = function
-            .encode_input(&ethabi_args)
-            .expect("Failed to encode input");
+        let (_, key_usage) = self.assert_action_allowed(&action);
 
         // Build the EVM transaction
-        let evm_transaction = EVMTransactionBuilder::new()
-            .chain_id(chain_id)
-            .nonce(nonce.0)
-            .max_priority_fee_per_gas(max_priority_fee_per_gas.0)
-            .max_fee_per_gas(max_fee_per_gas.0)
-            .gas_limit(gas_limit.0)
-            .to(contract_address)
-            .value(value.0)
-            .input(input_data.clone())
-            .access_list(access_list.clone())
-            .build();
-
-        let tx_bytes = evm_transaction.build_for_signing();
-
-        // Compute the hash of the serialized transaction
-        let hashed_payload_vec = keccak256(&tx_bytes);
-        // Ensure the vector has the correct length
-        let hashed_payload: [u8; 32] = hashed_payload_vec
-            .try_into()
-            .expect("Hash output should be 32 bytes");
-
-        // Log the details
-        env::log_str(&format!(
-            "LOG_STR_CHAIN_ID: {:?}
-            LOG_STR_NONCE: {:?}
-            LOG_STR_MAX_PRIORITY_FEE_PER_GAS: {:?}
-            LOG_STR_MAX_FEE_PER_GAS: {:?}
-            LOG_STR_GAS_LIMIT: {:?}
-            LOG_STR_CONTRACT: {:?}
-            LOG_STR_VALUE: {:?}
-            LOG_STR_INPUT: {:?}
-            LOG_STR_ACCESS_LIST: {:?}
-            LOG_STR_FUNCTION: {:?}
-            LOG_STR_ABI_PARAMS: {:?}
-            LOG_STR_ABI_ARGS: {:?}
-            LOG_STR_HASH: {:?},
-            LOG_STR_TXN_BYTES: {:?}",
+        let input_data = encode_function_call(method_name, method_params, args);
+        let tx_bytes = build_evm_transaction(
+            transaction_type,
             chain_id,
-            nonce.0,
-            max_priority_fee_per_gas.0,
-            max_fee_per_gas.0,
-            gas_limit.0,
             contract_address,
-            value.0,
             input_data,
+            gas_limit,
+            value,
+            nonce,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
             access_list,
-            function,
-            ethabi_params,
-            ethabi_args,
-            hashed_payload,
-            tx_bytes
-        ));
+        );
 
-        let request_payload =
-            create_sign_request_from_transaction(hashed_payload, &env::signer_account_pk());
+        // Compute the hash of the serialized transaction
+        let hashed_payload = keccak_hash_payload(&tx_bytes);
+        self.internal_record_signed_payload(&action.chain_id(), Some(nonce.0), hashed_payload);
 
-        // Call the MPC contract to get a signature
-        Promise::new(self.mpc_contract.clone())
-            .function_call_weight(
-                "sign".to_string(),
-                near_sdk::serde_json::to_vec(&request_payload).unwrap(),
-                NearToken::from_near(1),
-                Gas::from_tgas(30),
-                GasWeight(1),
-            )
-            .as_return()
+        self.internal_sign_payload(key_usage.trial_id, &action, hashed_payload)
     }
 }
@@ -1,4 +1,11 @@
 pub mod call_fn;
 pub mod serialization;
+pub mod sign_message;
+pub mod transaction;
+pub mod typed_data;
+pub mod user_operation;
 
 pub use serialization::*;
+pub use transaction::*;
+pub use typed_data::*;
+pub use user_operation::*;
@@ -81,53 +81,32 @@ impl From<SerializableToken> for Token {
         match st {
             // Handle Address parsing
             SerializableToken::Address(s) => {
-                env::log_str(&format!("Incoming Address value: {}", s));
-                let s = s.trim_start_matches("0x");
-                let bytes = hex::decode(s).expect("Invalid Address value");
+                let bytes = decode_hex("Address", &s);
                 if bytes.len() != 20 {
-                    panic!("Address must be 20 bytes");
+                    invalid_value("Address", &s);
                 }
-                env::log_str(&format!("Parsed Address value: {:?}", bytes));
                 Token::Address(Address::from_slice(&bytes))
             }
             // Handle FixedBytes parsing
             SerializableToken::FixedBytes(s) => {
-                env::log_str(&format!("Incoming FixedBytes value: {}", s));
-                let s = s.trim_start_matches("0x");
-                let bytes = hex::decode(s).expect("Invalid FixedBytes value");
-                env::log_str(&format!("Parsed FixedBytes value: {:?}", bytes));
+                let bytes = decode_hex("FixedBytes", &s);
                 Token::FixedBytes(bytes)
             }
             // Handle Bytes parsing
             SerializableToken::Bytes(s) => {
-                env::log_str(&format!("Incoming Bytes value: {}", s));
-                let s = s.trim_start_matches("0x");
-                let bytes = hex::decode(s).expect("Invalid Bytes value");
-                env::log_str(&format!("Parsed Bytes value: {:?}", bytes));
+                let bytes = decode_hex("Bytes", &s);
                 Token::Bytes(bytes)
             }
             // Handle Int parsing
             SerializableToken::Int(s) => {
-                env::log_str(&format!("Incoming Int value: {}", s));
-                let value = if s.starts_with("0x") {
-                    U256::from_str_radix(&s[2..], 16).expect("Invalid Int value")
-                } else {
-                    U256::from_str_radix(&s, 10).expect("Invalid Int value")
-                };
+                let value = parse_u256("Int", &s);
                 // Convert U256 to signed integer (assuming 256-bit signed integer)
                 let int_value = ethabi::Int::from(value);
-                env::log_str(&format!("Parsed Int value: {:?}", int_value));
                 Token::Int(int_value)
             }
             // Handle Uint parsing
             SerializableToken::Uint(s) => {
-                env::log_str(&format!("Incoming Uint value: {}", s));
-                let value = if s.starts_with("0x") {
-                    U256::from_str_radix(&s[2..], 16).expect("Invalid Uint value")
-                } else {
-                    U256::from_str_radix(&s, 10).expect("Invalid Uint value")
-                };
-                env::log_str(&format!("Parsed Uint value: {}", value));
+                let value = parse_u256("Uint", &s);
                 Token::Uint(value)
             }
             SerializableToken::Bool(b) => Token::Bool(b),
@@ -147,3 +126,26 @@ impl From<SerializableToken> for Token {
         }
     }
 }
+
+/// Panics with an `INVALID_ABI_VALUE` error for the given token kind.
+fn invalid_value(kind: &'static str, value: &str) -> ! {
+    TrialError::InvalidAbiValue {
+        kind,
+        value: value.to_string(),
+    }
+    .panic()
+}
+
+/// Decodes an optionally `0x` prefixed hex string.
+pub(crate) fn decode_hex(kind: &'static str, value: &str) -> Vec<u8> {
+    hex::decode(value.trim_start_matches("0x")).unwrap_or_else(|_| invalid_value(kind, value))
+}
+
+/// Parses a decimal or `0x` prefixed hex string into a `U256`.
+pub(crate) fn parse_u256(kind: &'static str, value: &str) -> U256 {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex_value) => U256::from_str_radix(hex_value, 16),
+        None => U256::from_str_radix(value, 10),
+    };
+    parsed.unwrap_or_else(|_| invalid_value(kind, value))
+}
@@ -0,0 +1,66 @@
+// trial_user/perform_actions/evm/sign_message.rs
+use crate::*;
+use serde_json::Value;
+
+/// Panics if a `personal_sign` message could be mistaken for a hash.
+/// Smart accounts accept the EIP-191 signature of a `userOpHash` as a user operation
+/// signature, so signing arbitrary 32 byte messages would bypass `sign_user_operation`.
+pub fn assert_personal_message_allowed(message: &[u8]) {
+    if message.len() == 32 {
+        TrialError::AmbiguousPersonalMessage.panic();
+    }
+}
+
+/// Builds the EIP-191 `personal_sign` message the MPC is asked to sign.
+pub fn build_evm_personal_message(message: &[u8]) -> Vec<u8> {
+    let mut payload = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    payload.extend_from_slice(message);
+    payload
+}
+
+#[near]
+impl Contract {
+    /// Signs a message with the EIP-191 `personal_sign` prefix, e.g. for a SIWE login.
+    /// The chain must allow personal messages, and 32 byte messages are rejected.
+    pub fn sign_evm_message(&mut self, chain_id: u64, message: String) -> Promise {
+        assert_personal_message_allowed(message.as_bytes());
+        let action = Action::SignMessage(SignMessageAction {
+            chain_id: ChainId::Evm(chain_id),
+            recipient: None,
+            primary_type: None,
+        });
+
+        let hashed_payload = keccak_hash_payload(&build_evm_personal_message(message.as_bytes()));
+        self.internal_sign_message(&action, hashed_payload)
+    }
+
+    /// Signs EIP-712 typed data, hashed on-chain from the `eth_signTypedData_v4` fields.
+    /// The domain's `chainId` selects the trial chain, and its `verifyingContract` and the
+    /// primary type must be allowed by that chain's constraints.
+    pub fn sign_evm_typed_data(
+        &mut self,
+        domain: Value,
+        types: TypedDataTypes,
+        primary_type: String,
+        message: Value,
+    ) -> Promise {
+        let action = Action::SignMessage(SignMessageAction {
+            chain_id: ChainId::Evm(typed_data_chain_id(&domain)),
+            recipient: typed_data_verifying_contract(&domain),
+            primary_type: Some(primary_type.clone()),
+        });
+
+        let payload = build_typed_data_payload(&domain, &types, &primary_type, &message);
+        let hashed_payload = keccak_hash_payload(&payload);
+        self.internal_sign_message(&action, hashed_payload)
+    }
+
+    /// Checks and records a message signature for the current trial key, then requests it.
+    /// Messages have no account nonce, so only identical messages are rejected.
+    fn internal_sign_message(&mut self, action: &Action, hashed_payload: [u8; 32]) -> Promise {
+        let (_, key_usage) = self.assert_action_allowed(action);
+        self.internal_record_signed_payload(&action.chain_id(), None, hashed_payload);
+
+        self.internal_sign_payload(key_usage.trial_id, action, hashed_payload)
+    }
+}
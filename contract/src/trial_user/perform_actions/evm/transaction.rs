@@ -0,0 +1,220 @@
+// trial_user/perform_actions/evm/transaction.rs
+use crate::*;
+use near_sdk::json_types::U128;
+use omni_transaction::evm::types::{AccessList, Address};
+
+/// Envelope an EVM transaction is signed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[near(serializers = [json, borsh])]
+#[serde(rename_all = "snake_case")]
+pub enum EvmTransactionType {
+    /// Type 0, priced with `gasPrice` and replay protected with EIP-155
+    Legacy,
+    /// Type 1 (EIP-2930), priced with `gasPrice` and carrying an access list
+    AccessList,
+    /// Type 2 (EIP-1559), priced with `maxFeePerGas` and `maxPriorityFeePerGas`
+    Eip1559,
+}
+
+/// Picks the fee parameters the transaction type is priced with.
+/// Returns `(max_fee_per_gas, max_priority_fee_per_gas)`. Legacy and EIP-2930 transactions
+/// are priced with the gas price as `max_fee_per_gas` and have no priority fee.
+pub fn evm_fee_params(
+    transaction_type: EvmTransactionType,
+    gas_price: Option<U128>,
+    max_fee_per_gas: Option<U128>,
+    max_priority_fee_per_gas: Option<U128>,
+) -> (U128, Option<U128>) {
+    match transaction_type {
+        EvmTransactionType::Legacy | EvmTransactionType::AccessList => {
+            let gas_price = gas_price
+                .unwrap_or_else(|| TrialError::MissingEvmFee { field: "gas_price" }.panic());
+            (gas_price, None)
+        }
+        EvmTransactionType::Eip1559 => (
+            max_fee_per_gas.unwrap_or_else(|| {
+                TrialError::MissingEvmFee {
+                    field: "max_fee_per_gas",
+                }
+                .panic()
+            }),
+            Some(max_priority_fee_per_gas.unwrap_or_else(|| {
+                TrialError::MissingEvmFee {
+                    field: "max_priority_fee_per_gas",
+                }
+                .panic()
+            })),
+        ),
+    }
+}
+
+/// Appends the RLP encoding of a byte string.
+fn rlp_append_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        out.push(bytes[0]);
+    } else {
+        rlp_append_length(out, bytes.len(), 0x80);
+        out.extend_from_slice(bytes);
+    }
+}
+
+/// Appends the RLP encoding of an integer, as big endian bytes without leading zeros.
+fn rlp_append_uint(out: &mut Vec<u8>, value: u128) {
+    let bytes = value.to_be_bytes();
+    let first_nonzero = bytes.iter().position(|byte| *byte != 0).unwrap_or(bytes.len());
+    rlp_append_bytes(out, &bytes[first_nonzero..]);
+}
+
+/// Appends the RLP encoding of a list whose items are already encoded in `payload`.
+fn rlp_append_list(out: &mut Vec<u8>, payload: &[u8]) {
+    rlp_append_length(out, payload.len(), 0xc0);
+    out.extend_from_slice(payload);
+}
+
+/// Appends a string (`offset` 0x80) or list (`offset` 0xc0) header.
+fn rlp_append_length(out: &mut Vec<u8>, len: usize, offset: u8) {
+    if len < 56 {
+        out.push(offset + len as u8);
+    } else {
+        let len_bytes = (len as u64).to_be_bytes();
+        let first_nonzero = len_bytes.iter().position(|byte| *byte != 0).unwrap_or(0);
+        let len_bytes = &len_bytes[first_nonzero..];
+        out.push(offset + 55 + len_bytes.len() as u8);
+        out.extend_from_slice(len_bytes);
+    }
+}
+
+/// Encodes an EIP-2930 access list as a list of `[address, [storage_key, ...]]`.
+fn rlp_append_access_list(out: &mut Vec<u8>, access_list: &AccessList) {
+    let mut items = Vec::new();
+    for (address, storage_keys) in access_list {
+        let mut keys = Vec::new();
+        for storage_key in storage_keys {
+            rlp_append_bytes(&mut keys, storage_key.as_ref());
+        }
+        let mut item = Vec::new();
+        rlp_append_bytes(&mut item, address.as_ref());
+        rlp_append_list(&mut item, &keys);
+        rlp_append_list(&mut items, &item);
+    }
+    rlp_append_list(out, &items);
+}
+
+/// Builds the EIP-155 signing payload of a legacy transaction:
+/// `rlp([nonce, gasPrice, gasLimit, to, value, data, chainId, 0, 0])`.
+pub fn build_legacy_evm_transaction(
+    chain_id: u64,
+    contract_address: Address,
+    input_data: Vec<u8>,
+    gas_limit: U128,
+    value: U128,
+    nonce: U64,
+    gas_price: U128,
+) -> Vec<u8> {
+    let mut fields = Vec::new();
+    rlp_append_uint(&mut fields, nonce.0 as u128);
+    rlp_append_uint(&mut fields, gas_price.0);
+    rlp_append_uint(&mut fields, gas_limit.0);
+    rlp_append_bytes(&mut fields, contract_address.as_ref());
+    rlp_append_uint(&mut fields, value.0);
+    rlp_append_bytes(&mut fields, &input_data);
+    rlp_append_uint(&mut fields, chain_id as u128);
+    rlp_append_uint(&mut fields, 0);
+    rlp_append_uint(&mut fields, 0);
+
+    let mut payload = Vec::new();
+    rlp_append_list(&mut payload, &fields);
+    payload
+}
+
+/// Builds the signing payload of an EIP-2930 transaction:
+/// `0x01 || rlp([chainId, nonce, gasPrice, gasLimit, to, value, data, accessList])`.
+#[allow(clippy::too_many_arguments)]
+pub fn build_access_list_evm_transaction(
+    chain_id: u64,
+    contract_address: Address,
+    input_data: Vec<u8>,
+    gas_limit: U128,
+    value: U128,
+    nonce: U64,
+    gas_price: U128,
+    access_list: &AccessList,
+) -> Vec<u8> {
+    let mut fields = Vec::new();
+    rlp_append_uint(&mut fields, chain_id as u128);
+    rlp_append_uint(&mut fields, nonce.0 as u128);
+    rlp_append_uint(&mut fields, gas_price.0);
+    rlp_append_uint(&mut fields, gas_limit.0);
+    rlp_append_bytes(&mut fields, contract_address.as_ref());
+    rlp_append_uint(&mut fields, value.0);
+    rlp_append_bytes(&mut fields, &input_data);
+    rlp_append_access_list(&mut fields, access_list);
+
+    let mut payload = vec![0x01];
+    rlp_append_list(&mut payload, &fields);
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPIENT: &str = "0x3535353535353535353535353535353535353535";
+
+    #[test]
+    fn legacy_transaction_matches_the_eip155_example() {
+        // nonce 9, 20 gwei gas price, 21,000 gas and 1 ether on chain 1
+        let payload = build_legacy_evm_transaction(
+            1,
+            parse_evm_address(RECIPIENT),
+            Vec::new(),
+            U128(21_000),
+            U128(1_000_000_000_000_000_000),
+            U64(9),
+            U128(20_000_000_000),
+        );
+
+        assert_eq!(
+            hex::encode(&payload),
+            "ec098504a817c800825208943535353535353535353535353535353535353535\
+             880de0b6b3a764000080018080"
+        );
+        assert_eq!(
+            hex::encode(keccak_hash_payload(&payload)),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+    }
+
+    #[test]
+    fn access_list_transaction_encodes_the_access_list() {
+        // The EIP-155 example as a type 1 transaction, warming two storage slots
+        let mut last_slot = [0u8; 32];
+        last_slot[31] = 1;
+        let access_list: AccessList = vec![(
+            parse_evm_address(RECIPIENT),
+            vec![[0u8; 32].into(), last_slot.into()],
+        )];
+        let payload = build_access_list_evm_transaction(
+            1,
+            parse_evm_address(RECIPIENT),
+            Vec::new(),
+            U128(30_000),
+            U128(1_000_000_000_000_000_000),
+            U64(9),
+            U128(20_000_000_000),
+            &access_list,
+        );
+
+        assert_eq!(
+            hex::encode(&payload),
+            "01f88701098504a817c800827530943535353535353535353535353535353535353535\
+             880de0b6b3a764000080f85bf859943535353535353535353535353535353535353535\
+             f842a00000000000000000000000000000000000000000000000000000000000000000\
+             a00000000000000000000000000000000000000000000000000000000000000001"
+        );
+        assert_eq!(
+            hex::encode(keccak_hash_payload(&payload)),
+            "c84a67cac98daf89bc726af3b66f3c23c42dc5eef75acffea2a66c2f0e374e00"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+// trial_user/perform_actions/evm/typed_data.rs
+use crate::perform_actions::serialization::{decode_hex, parse_u256};
+use crate::*;
+use ethabi::Token;
+use ethereum_types::U256;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Name of the struct type describing an EIP-712 domain
+pub const EIP712_DOMAIN_TYPE: &str = "EIP712Domain";
+
+/// A member of an EIP-712 struct type.
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+}
+
+/// Struct types by name, as in `eth_signTypedData_v4`. Must include `EIP712Domain`.
+pub type TypedDataTypes = HashMap<String, Vec<TypedDataField>>;
+
+/// Panics with an `INVALID_TYPED_DATA` error.
+fn invalid_typed_data(reason: String) -> ! {
+    TrialError::InvalidTypedData { reason }.panic()
+}
+
+/// Panics for a member whose value doesn't fit its type.
+fn invalid_member(field_type: &str, value: &Value) -> ! {
+    invalid_typed_data(format!("invalid `{}` value {}", field_type, value))
+}
+
+/// Strips one array suffix, e.g. `Person[]` or `uint256[3]` to `Person` or `uint256`.
+fn array_element_type(field_type: &str) -> Option<&str> {
+    field_type
+        .strip_suffix(']')
+        .and_then(|without_bracket| without_bracket.rfind('['))
+        .map(|index| &field_type[..index])
+}
+
+/// Base struct name of a field type with every array suffix removed.
+fn base_type(field_type: &str) -> &str {
+    field_type.split('[').next().unwrap_or(field_type)
+}
+
+/// Collects the struct types `primary_type` references, directly or not.
+fn collect_dependencies(primary_type: &str, types: &TypedDataTypes, found: &mut BTreeSet<String>) {
+    let Some(fields) = types.get(primary_type) else {
+        return;
+    };
+    if !found.insert(primary_type.to_string()) {
+        return;
+    }
+    for field in fields {
+        collect_dependencies(base_type(&field.field_type), types, found);
+    }
+}
+
+/// Encodes a struct type and the types it references, e.g. `Mail(Person from)Person(string name)`.
+pub fn encode_type(primary_type: &str, types: &TypedDataTypes) -> String {
+    let mut dependencies = BTreeSet::new();
+    collect_dependencies(primary_type, types, &mut dependencies);
+    dependencies.remove(primary_type);
+
+    // The primary type goes first, then its dependencies sorted by name
+    std::iter::once(primary_type)
+        .chain(dependencies.iter().map(String::as_str))
+        .map(|type_name| {
+            let fields: Vec<String> = types[type_name]
+                .iter()
+                .map(|field| format!("{} {}", field.field_type, field.name))
+                .collect();
+            format!("{}({})", type_name, fields.join(","))
+        })
+        .collect()
+}
+
+/// `hashStruct` from EIP-712: the hash of the type hash and the encoded members.
+pub fn hash_struct(primary_type: &str, data: &Value, types: &TypedDataTypes) -> [u8; 32] {
+    let fields = types.get(primary_type).unwrap_or_else(|| {
+        invalid_typed_data(format!("unknown type `{}`", primary_type))
+    });
+
+    let mut encoded = keccak_hash_payload(encode_type(primary_type, types).as_bytes()).to_vec();
+    for field in fields {
+        let value = data.get(&field.name).unwrap_or_else(|| {
+            invalid_typed_data(format!("missing `{}` in `{}`", field.name, primary_type))
+        });
+        encoded.extend_from_slice(&encode_value(&field.field_type, value, types));
+    }
+    keccak_hash_payload(&encoded)
+}
+
+/// Encodes a single member as a 32 byte word. Dynamic values, arrays and structs are hashed.
+fn encode_value(field_type: &str, value: &Value, types: &TypedDataTypes) -> [u8; 32] {
+    if let Some(element_type) = array_element_type(field_type) {
+        let elements = value
+            .as_array()
+            .unwrap_or_else(|| invalid_typed_data(format!("`{}` must be an array", field_type)));
+        let encoded: Vec<u8> = elements
+            .iter()
+            .flat_map(|element| encode_value(element_type, element, types))
+            .collect();
+        return keccak_hash_payload(&encoded);
+    }
+    if types.contains_key(field_type) {
+        return hash_struct(field_type, value, types);
+    }
+
+    let as_str = || value.as_str().unwrap_or_else(|| invalid_member(field_type, value));
+    let token = match field_type {
+        "string" => return keccak_hash_payload(as_str().as_bytes()),
+        "bytes" => return keccak_hash_payload(&decode_hex("bytes", as_str())),
+        "bool" => Token::Bool(value.as_bool().unwrap_or_else(|| invalid_member(field_type, value))),
+        "address" => {
+            let address = parse_evm_address(as_str());
+            Token::Address(ethabi::Address::from_slice(address.as_ref()))
+        }
+        _ if field_type.starts_with("bytes") => {
+            let mut bytes = decode_hex("bytes", as_str());
+            if bytes.len() > 32 {
+                invalid_member(field_type, value);
+            }
+            bytes.resize(32, 0);
+            Token::FixedBytes(bytes)
+        }
+        _ if field_type.starts_with("uint") => match parse_integer(value) {
+            Some((false, magnitude)) => Token::Uint(magnitude),
+            _ => invalid_member(field_type, value),
+        },
+        _ if field_type.starts_with("int") => match parse_integer(value) {
+            // Two's complement
+            Some((true, magnitude)) => Token::Int((!magnitude).overflowing_add(U256::one()).0),
+            Some((false, magnitude)) => Token::Int(magnitude),
+            None => invalid_member(field_type, value),
+        },
+        _ => invalid_typed_data(format!("unsupported type `{}`", field_type)),
+    };
+
+    ethabi::encode(&[token])
+        .try_into()
+        .unwrap_or_else(|_| invalid_typed_data(format!("`{}` is not a static type", field_type)))
+}
+
+/// Reads an integer given as a JSON number or a decimal or `0x` prefixed hex string.
+/// Returns its sign and magnitude.
+fn parse_integer(value: &Value) -> Option<(bool, U256)> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .map(|unsigned| (false, U256::from(unsigned)))
+            .or_else(|| number.as_i64().map(|signed| (true, U256::from(signed.unsigned_abs())))),
+        Value::String(string) => match string.strip_prefix('-') {
+            Some(magnitude) => Some((true, parse_u256("int", magnitude))),
+            None => Some((false, parse_u256("uint", string))),
+        },
+        _ => None,
+    }
+}
+
+/// Builds the EIP-712 encoding of typed data:
+/// `0x19 0x01 || domainSeparator || hashStruct(message)`.
+pub fn build_typed_data_payload(
+    domain: &Value,
+    types: &TypedDataTypes,
+    primary_type: &str,
+    message: &Value,
+) -> Vec<u8> {
+    let mut payload = vec![0x19, 0x01];
+    payload.extend_from_slice(&hash_struct(EIP712_DOMAIN_TYPE, domain, types));
+    payload.extend_from_slice(&hash_struct(primary_type, message, types));
+    payload
+}
+
+/// Reads the `chainId` of an EIP-712 domain, which selects the chain whose constraints apply.
+pub fn typed_data_chain_id(domain: &Value) -> u64 {
+    domain
+        .get("chainId")
+        .and_then(parse_integer)
+        .filter(|(negative, chain_id)| !negative && *chain_id <= U256::from(u64::MAX))
+        .map(|(_, chain_id)| chain_id.as_u64())
+        .unwrap_or_else(|| invalid_typed_data("the domain needs a valid `chainId`".to_string()))
+}
+
+/// Reads the normalized `verifyingContract` of an EIP-712 domain, if it has one.
+pub fn typed_data_verifying_contract(domain: &Value) -> Option<String> {
+    domain.get("verifyingContract").map(|contract| {
+        let contract = contract.as_str().unwrap_or_else(|| {
+            invalid_typed_data("`verifyingContract` must be a string".to_string())
+        });
+        convert_address_to_hex_string(&parse_evm_address(contract))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn payload_matches_the_eip712_mail_example() {
+        let types: TypedDataTypes = serde_json::from_value(json!({
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" }
+            ],
+            "Person": [
+                { "name": "name", "type": "string" },
+                { "name": "wallet", "type": "address" }
+            ],
+            "Mail": [
+                { "name": "from", "type": "Person" },
+                { "name": "to", "type": "Person" },
+                { "name": "contents", "type": "string" }
+            ]
+        }))
+        .unwrap();
+        let domain = json!({
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        });
+        let message = json!({
+            "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+            "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+            "contents": "Hello, Bob!"
+        });
+
+        assert_eq!(
+            encode_type("Mail", &types),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        let payload = build_typed_data_payload(&domain, &types, "Mail", &message);
+        assert_eq!(
+            hex::encode(&payload[2..34]),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(&payload[34..]),
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+        assert_eq!(
+            hex::encode(keccak_hash_payload(&payload)),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+}
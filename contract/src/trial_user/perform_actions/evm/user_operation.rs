@@ -0,0 +1,260 @@
+// trial_user/perform_actions/evm/user_operation.rs
+use crate::perform_actions::evm::sign_message::build_evm_personal_message;
+use crate::perform_actions::serialization::{decode_hex, parse_u256};
+use crate::*;
+use ethabi::{ParamType, Token};
+use ethereum_types::U256;
+use near_sdk::json_types::U128;
+use omni_transaction::evm::types::Address;
+
+/// Smart account method user operations must call, as in SimpleAccount and most forks.
+pub const USER_OPERATION_EXECUTE_SIGNATURE: &str = "execute(address,uint256,bytes)";
+
+/// An ERC-4337 user operation, in the EntryPoint v0.6 layout. Byte fields are hex strings.
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct UserOperation {
+    pub sender: String,
+    pub nonce: String, // Decimal or hex, as the key and sequence don't fit in a u128
+    pub init_code: String,
+    pub call_data: String,
+    pub call_gas_limit: U128,
+    pub verification_gas_limit: U128,
+    pub pre_verification_gas: U128,
+    pub max_fee_per_gas: U128,
+    pub max_priority_fee_per_gas: U128,
+    pub paymaster_and_data: String,
+}
+
+/// The call a smart account makes when it executes a user operation.
+pub struct UserOperationCall {
+    pub target: Address,
+    pub value: U128,
+    /// `0x` prefixed selector, or empty for a plain transfer.
+    pub selector: String,
+}
+
+/// Panics with an `INVALID_USER_OPERATION` error.
+fn invalid_user_operation(reason: &'static str) -> ! {
+    TrialError::InvalidUserOperation { reason }.panic()
+}
+
+/// Converts an address to its `ethabi` token.
+fn address_token(address: &Address) -> Token {
+    Token::Address(ethabi::Address::from_slice(address.as_ref()))
+}
+
+/// Hashes a byte field the way the entry point packs it.
+fn hashed_bytes_token(kind: &'static str, value: &str) -> Token {
+    Token::FixedBytes(keccak_hash_payload(&decode_hex(kind, value)).to_vec())
+}
+
+/// Reads the paymaster from the first 20 bytes of `paymasterAndData`, if there is one.
+pub fn user_operation_paymaster(user_op: &UserOperation) -> Option<Address> {
+    let paymaster_and_data = decode_hex("bytes", &user_op.paymaster_and_data);
+    if paymaster_and_data.is_empty() {
+        return None;
+    }
+    let paymaster: [u8; 20] = paymaster_and_data
+        .get(..20)
+        .and_then(|bytes| bytes.try_into().ok())
+        .unwrap_or_else(|| invalid_user_operation("`paymaster_and_data` is too short"));
+    Some(Address::from(paymaster))
+}
+
+/// Decodes the `execute(address,uint256,bytes)` call in a user operation's `callData`.
+pub fn decode_user_operation_call(user_op: &UserOperation) -> UserOperationCall {
+    let call_data = decode_hex("bytes", &user_op.call_data);
+    let execute_selector = &keccak_hash_payload(USER_OPERATION_EXECUTE_SIGNATURE.as_bytes())[..4];
+    if call_data.len() < 4 || &call_data[..4] != execute_selector {
+        invalid_user_operation("`call_data` must call `execute(address,uint256,bytes)`");
+    }
+
+    let tokens = ethabi::decode(
+        &[ParamType::Address, ParamType::Uint(256), ParamType::Bytes],
+        &call_data[4..],
+    )
+    .unwrap_or_else(|_| invalid_user_operation("`call_data` could not be decoded"));
+    let (Token::Address(target), Token::Uint(value), Token::Bytes(inner_call_data)) =
+        (&tokens[0], &tokens[1], &tokens[2])
+    else {
+        invalid_user_operation("`call_data` could not be decoded");
+    };
+
+    let selector = match inner_call_data.len() {
+        0 => String::new(),
+        1..=3 => invalid_user_operation("the executed call is shorter than a selector"),
+        _ => format!("0x{}", hex::encode(&inner_call_data[..4])),
+    };
+    let value = u128::try_from(*value)
+        .unwrap_or_else(|_| invalid_user_operation("the executed call's value is too large"));
+
+    UserOperationCall {
+        target: Address::from(target.0),
+        value: U128(value),
+        selector,
+    }
+}
+
+/// Computes the `userOpHash` the entry point exposes through `getUserOpHash`:
+/// `keccak256(abi.encode(keccak256(pack(userOp)), entryPoint, chainId))`.
+pub fn hash_user_operation(
+    user_op: &UserOperation,
+    entry_point: &Address,
+    chain_id: u64,
+) -> [u8; 32] {
+    let packed = ethabi::encode(&[
+        address_token(&parse_evm_address(&user_op.sender)),
+        Token::Uint(parse_u256("uint", &user_op.nonce)),
+        hashed_bytes_token("bytes", &user_op.init_code),
+        hashed_bytes_token("bytes", &user_op.call_data),
+        Token::Uint(U256::from(user_op.call_gas_limit.0)),
+        Token::Uint(U256::from(user_op.verification_gas_limit.0)),
+        Token::Uint(U256::from(user_op.pre_verification_gas.0)),
+        Token::Uint(U256::from(user_op.max_fee_per_gas.0)),
+        Token::Uint(U256::from(user_op.max_priority_fee_per_gas.0)),
+        hashed_bytes_token("bytes", &user_op.paymaster_and_data),
+    ]);
+
+    keccak_hash_payload(&ethabi::encode(&[
+        Token::FixedBytes(keccak_hash_payload(&packed).to_vec()),
+        address_token(entry_point),
+        Token::Uint(U256::from(chain_id)),
+    ]))
+}
+
+/// Builds the message the MPC is asked to sign for a user operation. Smart accounts
+/// check the owner's signature over the EIP-191 message of the `userOpHash`.
+pub fn build_user_operation_message(
+    user_op: &UserOperation,
+    entry_point: &Address,
+    chain_id: u64,
+) -> Vec<u8> {
+    build_evm_personal_message(&hash_user_operation(user_op, entry_point, chain_id))
+}
+
+#[near]
+impl Contract {
+    /// Signs an ERC-4337 user operation for the trial's smart account.
+    /// `sender` must be the account the key was activated with on the chain, and the entry
+    /// point must be allowed. The call it executes is checked like `call_evm_contract`, with
+    /// its selector matched against `allowed_selectors` and `max_gas` against `call_gas_limit`.
+    pub fn sign_user_operation(
+        &mut self,
+        chain_id: u64,
+        entry_point: String,
+        user_op: UserOperation,
+    ) -> Promise {
+        let entry_point = parse_evm_address(&entry_point);
+        let call = decode_user_operation_call(&user_op);
+
+        let account = self
+            .internal_get_key_usage(&env::signer_account_pk())
+            .unwrap_or_else(|| TrialError::AccessDenied.panic())
+            .evm_account(&ChainId::Evm(chain_id));
+        if parse_evm_address(&user_op.sender) != account {
+            invalid_user_operation("`sender` is not the trial account");
+        }
+
+        let overhead_gas = user_op
+            .verification_gas_limit
+            .0
+            .checked_add(user_op.pre_verification_gas.0)
+            .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
+        let action = Action::EVM(EvmAction {
+            chain_id,
+            method_name: call.selector,
+            contract_address: call.target,
+            gas_limit: user_op.call_gas_limit.0,
+            value: call.value,
+            max_fee_per_gas: user_op.max_fee_per_gas,
+            max_priority_fee_per_gas: Some(user_op.max_priority_fee_per_gas),
+            // The bundler picks the transaction envelope, so only the fee caps apply
+            transaction_type: None,
+            user_operation: Some(UserOperationContext {
+                entry_point,
+                paymaster: user_operation_paymaster(&user_op),
+                overhead_gas,
+            }),
+        });
+
+        let (_, key_usage) = self.assert_action_allowed(&action);
+
+        // The entry point tracks the smart account's nonce, so only replays are rejected here
+        let payload = build_user_operation_message(&user_op, &entry_point, chain_id);
+        let hashed_payload = keccak_hash_payload(&payload);
+        self.internal_record_signed_payload(&action.chain_id(), None, hashed_payload);
+
+        self.internal_sign_payload(key_usage.trial_id, &action, hashed_payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// EntryPoint v0.6, deployed at the same address on every chain
+    const ENTRY_POINT_V06: &str = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789";
+
+    /// A SimpleAccount `execute` of an ERC-20 `transfer` of 1,000,000 units.
+    fn user_operation() -> UserOperation {
+        UserOperation {
+            sender: format!("0x{}", "ab".repeat(20)),
+            nonce: "7".to_string(),
+            init_code: "0x".to_string(),
+            call_data: [
+                "0xb61d27f6",
+                "0000000000000000000000001111111111111111111111111111111111111111",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "0000000000000000000000000000000000000000000000000000000000000044",
+                "a9059cbb0000000000000000000000002222222222222222222222222222222222222222",
+                "00000000000000000000000000000000000000000000000000000000000f4240",
+                "00000000000000000000000000000000000000000000000000000000",
+            ]
+            .concat(),
+            call_gas_limit: U128(100_000),
+            verification_gas_limit: U128(150_000),
+            pre_verification_gas: U128(50_000),
+            max_fee_per_gas: U128(2_000_000_000),
+            max_priority_fee_per_gas: U128(1_000_000_000),
+            paymaster_and_data: "0x".to_string(),
+        }
+    }
+
+    #[test]
+    fn user_operation_hash_matches_the_entry_point() {
+        // `getUserOpHash` of EntryPoint v0.6 for the operation on Sepolia
+        let user_op_hash = hash_user_operation(
+            &user_operation(),
+            &parse_evm_address(ENTRY_POINT_V06),
+            11155111,
+        );
+        assert_eq!(
+            hex::encode(user_op_hash),
+            "a7d770bb44b7970751e48ca3e2eef21427c8001f54eec23595ef3686aff5cb72"
+        );
+
+        // Smart accounts check the owner's EIP-191 signature of the hash
+        let message = build_user_operation_message(
+            &user_operation(),
+            &parse_evm_address(ENTRY_POINT_V06),
+            11155111,
+        );
+        assert_eq!(
+            hex::encode(keccak_hash_payload(&message)),
+            "0f655edc156e50ab2c6343cf59a7c75d1e1d8cbeecdec3338994910af1ab9ae4"
+        );
+    }
+
+    #[test]
+    fn executed_call_is_decoded_from_the_call_data() {
+        let call = decode_user_operation_call(&user_operation());
+        assert_eq!(
+            convert_address_to_hex_string(&call.target),
+            format!("0x{}", "11".repeat(20))
+        );
+        assert_eq!(call.value.0, 0);
+        assert_eq!(call.selector, "0xa9059cbb");
+    }
+}
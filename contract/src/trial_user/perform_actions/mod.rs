@@ -1,5 +1,7 @@
 pub mod action_checker;
+pub mod bitcoin;
 pub mod evm;
 pub mod near;
+pub mod preview;
 
 pub use evm::*;
@@ -1,6 +1,37 @@
 // usage_tracking/usage_stats.rs
 use crate::*;
 
+/// Builds the NEAR function call transaction and returns the bytes the MPC is asked to sign.
+#[allow(clippy::too_many_arguments)]
+pub fn build_near_transaction(
+    account_id: &AccountId,
+    mpc_key: &PublicKey,
+    contract_id: &AccountId,
+    method_name: String,
+    args: Vec<u8>,
+    gas: Gas,
+    deposit: NearToken,
+    nonce: U64,
+    block_hash: Base58CryptoHash,
+) -> Vec<u8> {
+    let actions = vec![OmniAction::FunctionCall(Box::new(OmniFunctionCallAction {
+        method_name,
+        args,
+        gas: OmniU64(gas.as_gas()),
+        deposit: OmniU128(deposit.as_yoctonear()),
+    }))];
+
+    TransactionBuilder::new::<NEAR>()
+        .signer_id(account_id.to_string())
+        .signer_public_key(convert_pk_to_omni(mpc_key))
+        .nonce(nonce.0) // Use the provided nonce
+        .receiver_id(contract_id.to_string())
+        .block_hash(OmniBlockHash(block_hash.into()))
+        .actions(actions)
+        .build()
+        .build_for_signing()
+}
+
 #[near]
 impl Contract {
     /// Calls a NEAR contract via the MPC contract.
@@ -14,74 +45,38 @@ impl Contract {
         nonce: U64,
         block_hash: Base58CryptoHash,
     ) -> Promise {
+        let (network, account_id) = self
+            .internal_get_key_usage(&env::signer_account_pk())
+            .unwrap_or_else(|| TrialError::AccessDenied.panic())
+            .near_account();
+
         let action = Action::NEAR(NearAction {
+            network,
             method_name: method_name.clone(),
             contract_id: contract_id.clone(),
             gas_attached: gas,
             deposit_attached: deposit,
         });
 
-        let (trial_data, key_usage) = self.assert_action_allowed(&action);
-        let mpc_key = key_usage.mpc_key;
-        let account_id = match key_usage
-            .account_id_by_chain_id
-            .get(&ChainId("NEAR".to_string()))
-            .expect("Trial Account not activated")
-        {
-            UserAccountId::NEAR(account_id) => account_id.clone(),
-            _ => panic!("No NEAR account found"),
-        };
-
-        // Check exit conditions if any
-        if let Some(exit_conditions) = &trial_data.exit_conditions {
-            // Check transaction limit
-            if let Some(transaction_limit) = exit_conditions.transaction_limit {
-                if key_usage.usage_stats.total_interactions > transaction_limit {
-                    env::panic_str("Transaction limit reached");
-                }
-            }
-            // Additional exit conditions can be checked here
-        }
-
-        let actions = vec![OmniAction::FunctionCall(Box::new(OmniFunctionCallAction {
-            method_name: method_name.clone(),
-            args: args.clone(),
-            gas: OmniU64(gas.as_gas()),
-            deposit: OmniU128(deposit.as_yoctonear()),
-        }))];
+        let (_, key_usage) = self.assert_action_allowed(&action);
 
         // Build the NEAR transaction
-        let tx = TransactionBuilder::new::<NEAR>()
-            .signer_id(account_id.clone().to_string())
-            .signer_public_key(convert_pk_to_omni(&mpc_key))
-            .nonce(nonce.0) // Use the provided nonce
-            .receiver_id(contract_id.clone().to_string())
-            .block_hash(OmniBlockHash(block_hash.into()))
-            .actions(actions.clone())
-            .build()
-            .build_for_signing();
+        let tx = build_near_transaction(
+            &account_id,
+            &key_usage.mpc_key,
+            &contract_id,
+            method_name,
+            args,
+            gas,
+            deposit,
+            nonce,
+            block_hash,
+        );
 
         // Compute the SHA-256 hash of the serialized transaction
         let hashed_payload = hash_payload(&tx);
+        self.internal_record_signed_payload(&action.chain_id(), Some(nonce.0), hashed_payload);
 
-        // Log the details
-        env::log_str(&format!(
-            "Calling NEAR contract {:?} with method {:?}. Hash: {:?}",
-            contract_id, method_name, hashed_payload
-        ));
-
-        let request_payload =
-            create_sign_request_from_transaction(hashed_payload, &env::signer_account_pk());
-
-        // Call the MPC contract to get a signature
-        Promise::new(self.mpc_contract.clone())
-            .function_call_weight(
-                "sign".to_string(),
-                near_sdk::serde_json::to_vec(&request_payload).unwrap(),
-                NearToken::from_near(1),
-                Gas::from_tgas(30),
-                GasWeight(1),
-            )
-            .as_return()
+        self.internal_sign_payload(key_usage.trial_id, &action, hashed_payload)
     }
 }
@@ -0,0 +1,143 @@
+// trial_user/perform_actions/near/delegate.rs
+use crate::*;
+use near_sdk::borsh::BorshSerialize;
+
+/// Borsh variant index of `Action::FunctionCall` in NEAR transactions
+const FUNCTION_CALL_ACTION_INDEX: u8 = 2;
+
+/// Appends the borsh encoding of a value.
+fn borsh_append<T: BorshSerialize + ?Sized>(out: &mut Vec<u8>, value: &T) {
+    value
+        .serialize(out)
+        .unwrap_or_else(|_| TrialError::SerializationFailed.panic());
+}
+
+/// Builds the NEP-366 message the MPC is asked to sign: the NEP-461 prefix followed by the
+/// borsh `DelegateAction` wrapping a single function call.
+/// Relayers submit the `DelegateAction`, which is the payload without its first 4 bytes.
+#[allow(clippy::too_many_arguments)]
+pub fn build_near_delegate_action(
+    account_id: &AccountId,
+    mpc_key: &PublicKey,
+    contract_id: &AccountId,
+    method_name: String,
+    args: Vec<u8>,
+    gas: Gas,
+    deposit: NearToken,
+    nonce: U64,
+    max_block_height: U64,
+) -> Vec<u8> {
+    let mut payload = Vec::new();
+    borsh_append(&mut payload, &NEP_366_SIGNATURE_PREFIX);
+
+    borsh_append(&mut payload, account_id.as_str());
+    borsh_append(&mut payload, contract_id.as_str());
+    // A single non-delegate action
+    borsh_append(&mut payload, &1u32);
+    payload.push(FUNCTION_CALL_ACTION_INDEX);
+    borsh_append(&mut payload, &method_name);
+    borsh_append(&mut payload, &args);
+    borsh_append(&mut payload, &gas.as_gas());
+    borsh_append(&mut payload, &deposit.as_yoctonear());
+    borsh_append(&mut payload, &nonce.0);
+    borsh_append(&mut payload, &max_block_height.0);
+    // Key type followed by the key bytes, as NEAR encodes public keys
+    payload.extend_from_slice(mpc_key.as_bytes());
+
+    payload
+}
+
+#[near]
+impl Contract {
+    /// Signs a NEP-366 delegate action calling a NEAR contract, so a relayer can submit
+    /// it and pay for gas. The same constraints as `call_near_contract` apply.
+    pub fn sign_near_delegate_action(
+        &mut self,
+        contract_id: AccountId,
+        method_name: String,
+        args: Vec<u8>,
+        gas: Gas,
+        deposit: NearToken,
+        nonce: U64,
+        max_block_height: U64,
+    ) -> Promise {
+        let (network, account_id) = self
+            .internal_get_key_usage(&env::signer_account_pk())
+            .unwrap_or_else(|| TrialError::AccessDenied.panic())
+            .near_account();
+
+        let action = Action::NEAR(NearAction {
+            network,
+            method_name: method_name.clone(),
+            contract_id: contract_id.clone(),
+            gas_attached: gas,
+            deposit_attached: deposit,
+        });
+
+        let (_, key_usage) = self.assert_action_allowed(&action);
+
+        let payload = build_near_delegate_action(
+            &account_id,
+            &key_usage.mpc_key,
+            &contract_id,
+            method_name,
+            args,
+            gas,
+            deposit,
+            nonce,
+            max_block_height,
+        );
+
+        // Delegate actions share the access key's nonce with regular transactions
+        let hashed_payload = hash_payload(&payload);
+        self.internal_record_signed_payload(&action.chain_id(), Some(nonce.0), hashed_payload);
+
+        self.internal_sign_payload(key_usage.trial_id, &action, hashed_payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delegate_action_matches_the_nearcore_encoding() {
+        // The delegate action of nearcore's `DELEGATE_ACTION_HEX` test vector, with its
+        // `CreateAccount` swapped for a function call: `aaa` delegates to `bbb` with nonce 1,
+        // max block height 2 and an all-zero ED25519 key.
+        let payload = build_near_delegate_action(
+            &"aaa".parse().unwrap(),
+            &"ed25519:11111111111111111111111111111111".parse().unwrap(),
+            &"bbb".parse().unwrap(),
+            "ft_transfer".to_string(),
+            b"{}".to_vec(),
+            Gas::from_tgas(30),
+            NearToken::from_yoctonear(1),
+            U64(1),
+            U64(2),
+        );
+
+        assert_eq!(
+            hex::encode(payload),
+            [
+                // NEP-461 prefix, (1 << 30) + 366 as a little endian u32
+                "6e010040",
+                // Sender, receiver and a single action
+                "03000000616161",
+                "03000000626262",
+                "01000000",
+                // Function call: method name, args, gas and deposit
+                "02",
+                "0b00000066745f7472616e73666572",
+                "020000007b7d",
+                "00e057eb481b0000",
+                "01000000000000000000000000000000",
+                // Nonce, max block height and the public key
+                "0100000000000000",
+                "0200000000000000",
+                "000000000000000000000000000000000000000000000000000000000000000000",
+            ]
+            .concat()
+        );
+    }
+}
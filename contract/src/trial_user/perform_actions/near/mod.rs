@@ -1 +1,3 @@
 pub mod call_fn;
+pub mod delegate;
+pub mod sign_message;
@@ -0,0 +1,101 @@
+// trial_user/perform_actions/near/sign_message.rs
+use crate::*;
+use near_sdk::borsh::BorshSerialize;
+
+/// NEP-413 payload, borsh serialized after the tag.
+#[near(serializers = [borsh])]
+struct Nep413Payload {
+    message: String,
+    nonce: [u8; 32],
+    recipient: String,
+    callback_url: Option<String>,
+}
+
+/// Builds the NEP-413 message the MPC is asked to sign: the tag followed by the borsh payload.
+pub fn build_near_message(
+    message: String,
+    recipient: String,
+    nonce: Vec<u8>,
+    callback_url: Option<String>,
+) -> Vec<u8> {
+    let nonce: [u8; 32] = nonce
+        .try_into()
+        .unwrap_or_else(|_| TrialError::InvalidMessageNonce.panic());
+
+    let mut payload = NEP_413_SIGN_MESSAGE_PREFIX.to_le_bytes().to_vec();
+    Nep413Payload {
+        message,
+        nonce,
+        recipient,
+        callback_url,
+    }
+    .serialize(&mut payload)
+    .unwrap_or_else(|_| TrialError::SerializationFailed.panic());
+    payload
+}
+
+#[near]
+impl Contract {
+    /// Signs a NEP-413 message with the trial's NEAR account, e.g. to sign in to a dapp.
+    /// `nonce` must be 32 bytes. The recipient must be allowed by the trial's NEAR constraints.
+    pub fn sign_near_message(
+        &mut self,
+        message: String,
+        recipient: String,
+        nonce: Vec<u8>,
+        callback_url: Option<String>,
+    ) -> Promise {
+        let (network, _) = self
+            .internal_get_key_usage(&env::signer_account_pk())
+            .unwrap_or_else(|| TrialError::AccessDenied.panic())
+            .near_account();
+
+        let action = Action::SignMessage(SignMessageAction {
+            chain_id: ChainId::Near { network },
+            recipient: Some(recipient.clone()),
+            primary_type: None,
+        });
+
+        let (_, key_usage) = self.assert_action_allowed(&action);
+
+        let payload = build_near_message(message, recipient, nonce, callback_url);
+
+        // Messages have no account nonce, so only identical messages are rejected
+        let hashed_payload = hash_payload(&payload);
+        self.internal_record_signed_payload(&action.chain_id(), None, hashed_payload);
+
+        self.internal_sign_payload(key_usage.trial_id, &action, hashed_payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_is_prefixed_with_the_nep413_tag() {
+        let payload = build_near_message(
+            "Login to app".to_string(),
+            "app.near".to_string(),
+            vec![7; 32],
+            Some("https://app.near.org/callback".to_string()),
+        );
+
+        assert_eq!(
+            hex::encode(payload),
+            [
+                // (1 << 31) + 413 as a little endian u32
+                "9d010080",
+                // Message
+                "0c0000004c6f67696e20746f20617070",
+                // Nonce
+                "0707070707070707070707070707070707070707070707070707070707070707",
+                // Recipient
+                "080000006170702e6e656172",
+                // Callback URL
+                "011d00000068747470733a2f2f6170702e6e6561722e6f72672f63616c6c6261636b",
+            ]
+            .concat()
+        );
+    }
+}
@@ -0,0 +1,269 @@
+// trial_user/perform_actions/preview.rs
+use crate::perform_actions::bitcoin::transfer::{
+    build_bitcoin_transfer, BitcoinInput, BitcoinOutput,
+};
+use crate::perform_actions::evm::call_fn::{build_evm_transaction, encode_function_call};
+use crate::perform_actions::evm::sign_message::{
+    assert_personal_message_allowed, build_evm_personal_message,
+};
+use crate::perform_actions::near::call_fn::build_near_transaction;
+use crate::perform_actions::near::delegate::build_near_delegate_action;
+use crate::perform_actions::near::sign_message::build_near_message;
+use crate::perform_actions::serialization::{SerializableParam, SerializableToken};
+use crate::*;
+use near_sdk::json_types::U128;
+use omni_transaction::evm::types::AccessList;
+use serde_json::Value;
+
+/// The exact payload a `call_*` method would send to the MPC contract.
+#[derive(Clone)]
+#[near(serializers = [json])]
+pub struct TransactionPreview {
+    /// Serialized transaction, as returned by `build_for_signing`, or for Bitcoin the
+    /// input's BIP-143 preimage.
+    pub payload: Vec<u8>,
+    /// Hash handed to the MPC contract (sha256 for NEAR, keccak256 for EVM,
+    /// double sha256 for Bitcoin).
+    pub hash: Vec<u8>,
+    /// Derivation path of the MPC key.
+    pub path: String,
+}
+
+#[near]
+impl Contract {
+    /// Previews the payload `call_near_contract` would ask the MPC contract to sign.
+    pub fn preview_near_transaction(
+        &self,
+        public_key: PublicKey,
+        contract_id: AccountId,
+        method_name: String,
+        args: Vec<u8>,
+        gas: Gas,
+        deposit: NearToken,
+        nonce: U64,
+        block_hash: Base58CryptoHash,
+    ) -> TransactionPreview {
+        let key_usage = self
+            .internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+
+        let payload = build_near_transaction(
+            &key_usage.near_account().1,
+            &key_usage.mpc_key,
+            &contract_id,
+            method_name,
+            args,
+            gas,
+            deposit,
+            nonce,
+            block_hash,
+        );
+
+        TransactionPreview {
+            hash: hash_payload(&payload).to_vec(),
+            payload,
+            path: public_key_to_string(&public_key),
+        }
+    }
+
+    /// Previews the payload `sign_near_delegate_action` would ask the MPC contract to sign.
+    pub fn preview_near_delegate_action(
+        &self,
+        public_key: PublicKey,
+        contract_id: AccountId,
+        method_name: String,
+        args: Vec<u8>,
+        gas: Gas,
+        deposit: NearToken,
+        nonce: U64,
+        max_block_height: U64,
+    ) -> TransactionPreview {
+        let key_usage = self
+            .internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+
+        let payload = build_near_delegate_action(
+            &key_usage.near_account().1,
+            &key_usage.mpc_key,
+            &contract_id,
+            method_name,
+            args,
+            gas,
+            deposit,
+            nonce,
+            max_block_height,
+        );
+
+        TransactionPreview {
+            hash: hash_payload(&payload).to_vec(),
+            payload,
+            path: public_key_to_string(&public_key),
+        }
+    }
+
+    /// Previews the message `sign_near_message` would ask the MPC contract to sign.
+    pub fn preview_near_message(
+        &self,
+        public_key: PublicKey,
+        message: String,
+        recipient: String,
+        nonce: Vec<u8>,
+        callback_url: Option<String>,
+    ) -> TransactionPreview {
+        self.internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+
+        let payload = build_near_message(message, recipient, nonce, callback_url);
+
+        TransactionPreview {
+            hash: hash_payload(&payload).to_vec(),
+            payload,
+            path: public_key_to_string(&public_key),
+        }
+    }
+
+    /// Previews the payload `call_evm_contract` would ask the MPC contract to sign.
+    pub fn preview_evm_transaction(
+        &self,
+        public_key: PublicKey,
+        chain_id: u64,
+        contract_address: String,
+        method_name: String,
+        method_params: Vec<SerializableParam>,
+        args: Vec<SerializableToken>,
+        gas_limit: Option<U128>,
+        value: U128,
+        nonce: U64,
+        transaction_type: Option<EvmTransactionType>,
+        gas_price: Option<U128>,
+        max_fee_per_gas: Option<U128>,
+        max_priority_fee_per_gas: Option<U128>,
+        access_list: AccessList,
+    ) -> TransactionPreview {
+        self.internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+
+        let registry_chain_id = ChainId::Evm(chain_id);
+        let gas_limit = self.internal_gas_limit(&registry_chain_id, gas_limit);
+        let transaction_type =
+            self.internal_evm_transaction_type(&registry_chain_id, transaction_type);
+        let (max_fee_per_gas, max_priority_fee_per_gas) = evm_fee_params(
+            transaction_type,
+            gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        );
+        self.assert_evm_fees_allowed(
+            &registry_chain_id,
+            transaction_type,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        );
+
+        let input_data = encode_function_call(method_name, method_params, args);
+        let payload = build_evm_transaction(
+            transaction_type,
+            chain_id,
+            parse_evm_address(&contract_address),
+            input_data,
+            gas_limit,
+            value,
+            nonce,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            access_list,
+        );
+
+        TransactionPreview {
+            hash: keccak_hash_payload(&payload).to_vec(),
+            payload,
+            path: public_key_to_string(&public_key),
+        }
+    }
+
+    /// Previews the payload `sign_evm_message` would ask the MPC contract to sign.
+    pub fn preview_evm_message(
+        &self,
+        public_key: PublicKey,
+        message: String,
+    ) -> TransactionPreview {
+        self.internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+
+        assert_personal_message_allowed(message.as_bytes());
+        let payload = build_evm_personal_message(message.as_bytes());
+
+        TransactionPreview {
+            hash: keccak_hash_payload(&payload).to_vec(),
+            payload,
+            path: public_key_to_string(&public_key),
+        }
+    }
+
+    /// Previews the payload `sign_evm_typed_data` would ask the MPC contract to sign.
+    pub fn preview_evm_typed_data(
+        &self,
+        public_key: PublicKey,
+        domain: Value,
+        types: TypedDataTypes,
+        primary_type: String,
+        message: Value,
+    ) -> TransactionPreview {
+        self.internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+
+        let payload = build_typed_data_payload(&domain, &types, &primary_type, &message);
+
+        TransactionPreview {
+            hash: keccak_hash_payload(&payload).to_vec(),
+            payload,
+            path: public_key_to_string(&public_key),
+        }
+    }
+
+    /// Previews the payload `sign_user_operation` would ask the MPC contract to sign.
+    pub fn preview_user_operation(
+        &self,
+        public_key: PublicKey,
+        chain_id: u64,
+        entry_point: String,
+        user_op: UserOperation,
+    ) -> TransactionPreview {
+        self.internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic());
+
+        let payload =
+            build_user_operation_message(&user_op, &parse_evm_address(&entry_point), chain_id);
+
+        TransactionPreview {
+            hash: keccak_hash_payload(&payload).to_vec(),
+            payload,
+            path: public_key_to_string(&public_key),
+        }
+    }
+
+    /// Previews the sighashes `call_bitcoin_transfer` would ask the MPC contract to sign,
+    /// one per input. Each payload is the input's BIP-143 preimage.
+    pub fn preview_bitcoin_transfer(
+        &self,
+        public_key: PublicKey,
+        chain_id: ChainId,
+        inputs: Vec<BitcoinInput>,
+        outputs: Vec<BitcoinOutput>,
+    ) -> Vec<TransactionPreview> {
+        let account = self
+            .internal_get_key_usage(&public_key)
+            .unwrap_or_else(|| TrialError::AccessDenied.panic())
+            .bitcoin_account(&chain_id);
+        let (_, preimages) = build_bitcoin_transfer(&account, chain_id, &inputs, &outputs);
+
+        preimages
+            .into_iter()
+            .map(|payload| TransactionPreview {
+                hash: double_sha256(&payload).to_vec(),
+                payload,
+                path: public_key_to_string(&public_key),
+            })
+            .collect()
+    }
+}
@@ -1,7 +1,10 @@
 // utils.rs
 
 use crate::*;
-use near_sdk::{env, CurveType, PublicKey};
+use bech32::{u5, ToBase32, Variant};
+use hex::FromHex;
+use near_sdk::json_types::U128;
+use near_sdk::{env, CurveType, PromiseResult, PublicKey};
 use omni_transaction::{
     evm::types::Address,
     near::types::{
@@ -9,9 +12,18 @@ use omni_transaction::{
         Secp256K1PublicKey as OmniSECP256K1PublicKey,
     },
 };
+use ripemd::Ripemd160;
 use sha2::{Digest, Sha256};
 use views::ExtTrialData;
 
+/// Panics with an `INVALID_PUBLIC_KEY` error.
+fn invalid_public_key(pk: &PublicKey) -> ! {
+    TrialError::InvalidPublicKey {
+        public_key: public_key_to_string(pk),
+    }
+    .panic()
+}
+
 /// Converts a NEAR `PublicKey` to an OmniTransaction `PublicKey`.
 pub fn convert_pk_to_omni(pk: &PublicKey) -> OmniPublicKey {
     // First byte is the curve type
@@ -22,26 +34,18 @@ pub fn convert_pk_to_omni(pk: &PublicKey) -> OmniPublicKey {
 
     match curve_type {
         CurveType::ED25519 => {
-            if public_key_data.len() == ED25519_PUBLIC_KEY_LENGTH {
-                let ed25519_key: [u8; ED25519_PUBLIC_KEY_LENGTH] = public_key_data
-                    .try_into()
-                    .expect("Failed to convert ED25519 public key");
-
-                OmniPublicKey::ED25519(OmniEd25519PublicKey::from(ed25519_key))
-            } else {
-                env::panic_str("Invalid ED25519 public key length");
-            }
+            let ed25519_key: [u8; ED25519_PUBLIC_KEY_LENGTH] = public_key_data
+                .try_into()
+                .unwrap_or_else(|_| invalid_public_key(pk));
+
+            OmniPublicKey::ED25519(OmniEd25519PublicKey::from(ed25519_key))
         }
         CurveType::SECP256K1 => {
-            if public_key_data.len() == SECP256K1_PUBLIC_KEY_LENGTH {
-                let secp256k1_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH] = public_key_data
-                    .try_into()
-                    .expect("Failed to convert SECP256K1 public key");
-
-                OmniPublicKey::SECP256K1(OmniSECP256K1PublicKey::from(secp256k1_key))
-            } else {
-                env::panic_str("Invalid SECP256K1 public key length");
-            }
+            let secp256k1_key: [u8; SECP256K1_PUBLIC_KEY_LENGTH] = public_key_data
+                .try_into()
+                .unwrap_or_else(|_| invalid_public_key(pk));
+
+            OmniPublicKey::SECP256K1(OmniSECP256K1PublicKey::from(secp256k1_key))
         }
     }
 }
@@ -56,6 +60,23 @@ pub fn public_key_to_string(public_key: &PublicKey) -> String {
     }
 }
 
+/// Returns the day index (days since the Unix epoch) of a nanosecond timestamp.
+pub fn day_index(timestamp: u64) -> u64 {
+    timestamp / NANOSECONDS_PER_DAY
+}
+
+/// Drops per-day buckets that fall outside the rolling usage window ending at `day`.
+pub fn prune_day_buckets(buckets: &mut HashMap<u64, u64>, day: u64) {
+    buckets.retain(|bucket_day, _| bucket_day + USAGE_STATS_DAY_WINDOW > day);
+}
+
+/// Cost of storing `bytes` bytes on chain.
+pub fn storage_cost(bytes: u64) -> NearToken {
+    env::storage_byte_cost()
+        .checked_mul(bytes as u128)
+        .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic())
+}
+
 /// Hashes a payload using SHA256 and returns a 32-byte array.
 pub fn hash_payload(payload: &[u8]) -> [u8; 32] {
     let mut hasher = Sha256::new();
@@ -64,6 +85,45 @@ pub fn hash_payload(payload: &[u8]) -> [u8; 32] {
     result.into() // Converts the resulting hash into a [u8; 32] array
 }
 
+/// Hashes a payload with SHA256 twice, as Bitcoin sighashes are.
+pub fn double_sha256(payload: &[u8]) -> [u8; 32] {
+    hash_payload(&hash_payload(payload))
+}
+
+/// Compresses a secp256k1 public key to its 33 byte SEC1 form.
+/// Panics if the key is not a secp256k1 key.
+pub fn compress_secp256k1_public_key(public_key: &PublicKey) -> [u8; 33] {
+    if public_key.curve_type() != CurveType::SECP256K1 {
+        TrialError::InvalidBitcoinPublicKey {
+            public_key: public_key_to_string(public_key),
+        }
+        .panic();
+    }
+
+    // The key is stored as x || y, so only the parity of y is kept
+    let (x, y) = public_key.as_bytes()[1..].split_at(32);
+    let mut compressed = [0u8; 33];
+    compressed[0] = if y[31] % 2 == 0 { 0x02 } else { 0x03 };
+    compressed[1..].copy_from_slice(x);
+    compressed
+}
+
+/// Derives the P2WPKH account for a compressed secp256k1 public key.
+pub fn bitcoin_account_from_public_key(public_key: &[u8; 33], hrp: &str) -> BitcoinAccount {
+    // HASH160 is RIPEMD160 over SHA256
+    let pubkey_hash: [u8; 20] = Ripemd160::digest(hash_payload(public_key)).into();
+
+    // Witness version 0 followed by the witness program
+    let mut data = vec![u5::try_from_u8(0).unwrap()];
+    data.extend(pubkey_hash.to_base32());
+    let address = bech32::encode(hrp, data, Variant::Bech32).unwrap();
+
+    BitcoinAccount {
+        address,
+        pubkey_hash,
+    }
+}
+
 /// Creates a sign request from a hashed payload and public key.
 pub fn create_sign_request_from_transaction(
     hashed_payload: [u8; 32],
@@ -80,11 +140,127 @@ pub fn create_sign_request_from_transaction(
     serde_json::json!({ "request": sign_request })
 }
 
+/// Hashes a payload using Keccak-256, as EVM chains expect, and returns a 32-byte array.
+pub fn keccak_hash_payload(payload: &[u8]) -> [u8; 32] {
+    env::keccak256_array(payload)
+}
+
+/// Parses an optionally `0x` prefixed hex string into an Ethereum address.
+pub fn parse_evm_address(address: &str) -> Address {
+    let addr_bytes: [u8; 20] = <[u8; 20]>::from_hex(address.trim_start_matches("0x"))
+        .unwrap_or_else(|_| {
+            TrialError::InvalidEvmAddress {
+                address: address.to_string(),
+            }
+            .panic()
+        });
+    Address::from(addr_bytes)
+}
+
+/// Parses a hex encoded 4 byte function selector.
+pub fn parse_selector(selector: &str) -> [u8; 4] {
+    <[u8; 4]>::from_hex(selector.trim_start_matches("0x")).unwrap_or_else(|_| {
+        TrialError::InvalidAbiValue {
+            kind: "bytes4",
+            value: selector.to_string(),
+        }
+        .panic()
+    })
+}
+
 // Utility function to convert an Ethereum address to a normalized hex string
 pub fn convert_address_to_hex_string(address: &Address) -> String {
     format!("0x{}", hex::encode(address))
 }
 
+/// Comma separated method names for trial access keys.
+pub fn trial_access_key_methods() -> String {
+    TRIAL_USER_METHODS.join(",")
+}
+
+/// Panics if the contract is calling itself, which is what a trial access key does.
+/// Creator methods must not be reachable that way, otherwise a trial key could act
+/// as the contract account.
+pub fn assert_not_self_call() {
+    if env::predecessor_account_id() == env::current_account_id() {
+        TrialError::SelfCallNotAllowed.panic();
+    }
+}
+
+/// Validates the chain constraints passed by a creator and converts them to their stored form.
+pub fn parse_chain_constraints(
+    chain_constraints: HashMap<ChainId, ExtChainConstraints>,
+) -> HashMap<ChainId, ChainConstraints> {
+    let mut constraints_by_chain_id = HashMap::new();
+
+    for (chain_id, ext_constraints) in chain_constraints {
+        let chain_constraints = match (&chain_id, ext_constraints) {
+            (ChainId::Near { .. }, ExtChainConstraints::NEAR(near_constraints)) => {
+                ChainConstraints::NEAR(near_constraints)
+            }
+            (ChainId::Evm(_), ExtChainConstraints::EVM(ext_evm_constraints)) => {
+                let allowed_addresses = ext_evm_constraints
+                    .allowed_contracts
+                    .iter()
+                    .map(|addr_str| {
+                        let addr_str = addr_str.trim_start_matches("0x");
+                        let addr_bytes: [u8; 20] =
+                            <[u8; 20]>::from_hex(addr_str).unwrap_or_else(|_| {
+                                TrialError::InvalidEvmAddress {
+                                    address: addr_str.to_string(),
+                                }
+                                .panic()
+                            });
+                        Address::from(addr_bytes)
+                    })
+                    .collect();
+                let evm_constraints = EvmConstraints {
+                    allowed_methods: ext_evm_constraints.allowed_methods,
+                    allowed_contracts: allowed_addresses,
+                    allowed_selectors: ext_evm_constraints
+                        .allowed_selectors
+                        .iter()
+                        .map(|selector| parse_selector(selector))
+                        .collect(),
+                    max_gas: ext_evm_constraints.max_gas,
+                    max_value: ext_evm_constraints.max_value,
+                    initial_deposit: U128(
+                        ext_evm_constraints.initial_deposit.parse().unwrap_or_else(|_| {
+                            TrialError::InvalidAmount {
+                                amount: ext_evm_constraints.initial_deposit.clone(),
+                            }
+                            .panic()
+                        }),
+                    ),
+                    max_fee_per_gas_cap: ext_evm_constraints.max_fee_per_gas_cap,
+                    max_priority_fee_cap: ext_evm_constraints.max_priority_fee_cap,
+                    allow_personal_sign: ext_evm_constraints.allow_personal_sign,
+                    allowed_verifying_contracts: ext_evm_constraints.allowed_verifying_contracts,
+                    allowed_primary_types: ext_evm_constraints.allowed_primary_types,
+                    allowed_paymasters: ext_evm_constraints.allowed_paymasters,
+                    allowed_entry_points: ext_evm_constraints.allowed_entry_points,
+                };
+                ChainConstraints::EVM(evm_constraints)
+            }
+            (ChainId::Bitcoin { .. }, ExtChainConstraints::Bitcoin(bitcoin_constraints)) => {
+                ChainConstraints::Bitcoin(bitcoin_constraints)
+            }
+            _ => TrialError::ConstraintsMismatch {
+                chain_id: chain_id.to_string(),
+                expected: match chain_id {
+                    ChainId::Near { .. } => "NEAR",
+                    ChainId::Evm(_) => "EVM",
+                    ChainId::Bitcoin { .. } => "Bitcoin",
+                },
+            }
+            .panic(),
+        };
+        constraints_by_chain_id.insert(chain_id, chain_constraints);
+    }
+
+    constraints_by_chain_id
+}
+
 /// Function to convert addresses in TrialData to hex strings for easy comparison
 pub fn trial_data_to_ext_trial_data(trial_data: TrialData) -> ExtTrialData {
     let constraints_by_chain_id = trial_data
@@ -104,12 +280,27 @@ pub fn trial_data_to_ext_trial_data(trial_data: TrialData) -> ExtTrialData {
                     let ext_evm_constraints = ExtEvmConstraints {
                         allowed_methods: evm_constraints.allowed_methods,
                         allowed_contracts,
+                        allowed_selectors: evm_constraints
+                            .allowed_selectors
+                            .iter()
+                            .map(|selector| format!("0x{}", hex::encode(selector)))
+                            .collect(),
                         max_gas: evm_constraints.max_gas,
                         max_value: evm_constraints.max_value,
-                        initial_deposit: evm_constraints.initial_deposit,
+                        initial_deposit: evm_constraints.initial_deposit.0.to_string(),
+                        max_fee_per_gas_cap: evm_constraints.max_fee_per_gas_cap,
+                        max_priority_fee_cap: evm_constraints.max_priority_fee_cap,
+                        allow_personal_sign: evm_constraints.allow_personal_sign,
+                        allowed_verifying_contracts: evm_constraints.allowed_verifying_contracts,
+                        allowed_primary_types: evm_constraints.allowed_primary_types,
+                        allowed_paymasters: evm_constraints.allowed_paymasters,
+                        allowed_entry_points: evm_constraints.allowed_entry_points,
                     };
                     ExtChainConstraints::EVM(ext_evm_constraints)
                 }
+                ChainConstraints::Bitcoin(bitcoin_constraints) => {
+                    ExtChainConstraints::Bitcoin(bitcoin_constraints)
+                }
             };
             (chain_id, ext_constraints)
         })
@@ -122,12 +313,351 @@ pub fn trial_data_to_ext_trial_data(trial_data: TrialData) -> ExtTrialData {
         exit_conditions: trial_data.exit_conditions,
         expiration_time: trial_data.expiration_time,
         creator_account_id: trial_data.creator_account_id,
+        balance: trial_data.balance,
+        paused: trial_data.paused,
+        managers: trial_data.managers,
+        key_allowance: trial_data.key_allowance,
     }
 }
 
+/// Emits `ActionSigned` for every payload the current trial key signs for an action.
+fn emit_action_signed(trial_id: TrialId, action: &Action, hashed_payloads: &[[u8; 32]]) {
+    TrialEvent::ActionSigned(
+        hashed_payloads
+            .iter()
+            .map(|hashed_payload| ActionSignedData {
+                trial_id,
+                public_key: env::signer_account_pk(),
+                chain_id: action.chain_id(),
+                method: action.method_name().to_string(),
+                contract: action.contract(),
+                payload_hash: hex::encode(hashed_payload),
+            })
+            .collect(),
+    )
+    .emit();
+}
+
 #[near]
 impl Contract {
+    /// Reads a trial in its current layout.
+    pub(crate) fn internal_get_trial(&self, trial_id: TrialId) -> Option<TrialData> {
+        self.trial_data_by_id
+            .get(&trial_id)
+            .map(VersionedTrialData::to_current)
+            .or_else(|| {
+                read_legacy_entry::<_, TrialDataV1>(StorageKeys::TrialDataById, &trial_id)
+                    .map(TrialData::from)
+            })
+    }
+
+    /// Mutable access to a trial, upgrading its stored layout if needed.
+    pub(crate) fn internal_get_trial_mut(&mut self, trial_id: TrialId) -> Option<&mut TrialData> {
+        if !self.trial_data_by_id.contains_key(&trial_id) {
+            if let Some(trial_data) =
+                take_legacy_entry::<_, TrialDataV1>(StorageKeys::TrialDataById, &trial_id)
+            {
+                self.trial_data_by_id
+                    .insert(trial_id, VersionedTrialData::V1(trial_data));
+            }
+        }
+        self.trial_data_by_id
+            .get_mut(&trial_id)
+            .map(VersionedTrialData::upgrade)
+    }
+
+    /// Removes a trial and its stats, returning the trial.
+    pub(crate) fn internal_remove_trial(&mut self, trial_id: TrialId) -> Option<TrialData> {
+        self.trial_stats_by_id.remove(&trial_id);
+        self.trial_stats_by_id.flush();
+
+        let legacy_trial_data =
+            take_legacy_entry::<_, TrialDataV1>(StorageKeys::TrialDataById, &trial_id);
+        let trial_data = self.trial_data_by_id.remove(&trial_id);
+        self.trial_data_by_id.flush();
+        trial_data
+            .map(|trial_data| trial_data.to_current())
+            .or_else(|| legacy_trial_data.map(TrialData::from))
+    }
+
+    /// Reads a key's usage in its current layout.
+    pub(crate) fn internal_get_key_usage(&self, public_key: &PublicKey) -> Option<KeyUsage> {
+        self.key_usage_by_pk
+            .get(public_key)
+            .map(VersionedKeyUsage::to_current)
+            .or_else(|| {
+                read_legacy_entry::<_, KeyUsageV1>(StorageKeys::KeyUsageByPK, public_key)
+                    .map(KeyUsage::from)
+            })
+    }
+
+    /// Mutable access to a key's usage, upgrading its stored layout if needed.
+    pub(crate) fn internal_get_key_usage_mut(
+        &mut self,
+        public_key: &PublicKey,
+    ) -> Option<&mut KeyUsage> {
+        if !self.key_usage_by_pk.contains_key(public_key) {
+            if let Some(key_usage) =
+                take_legacy_entry::<_, KeyUsageV1>(StorageKeys::KeyUsageByPK, public_key)
+            {
+                // Keys of the first version weren't indexed by trial
+                self.internal_add_keys_to_trial(key_usage.trial_id, &[public_key.clone()]);
+                self.key_usage_by_pk
+                    .insert(public_key.clone(), VersionedKeyUsage::V1(key_usage));
+            }
+        }
+        self.key_usage_by_pk
+            .get_mut(public_key)
+            .map(VersionedKeyUsage::upgrade)
+    }
+
+    /// Removes a key's usage. The caller flushes `key_usage_by_pk`.
+    pub(crate) fn internal_remove_key_usage(&mut self, public_key: &PublicKey) {
+        take_legacy_entry::<_, KeyUsageV1>(StorageKeys::KeyUsageByPK, public_key);
+        self.key_usage_by_pk.remove(public_key);
+    }
+
+    /// Fetches a trial, panicking unless the caller is its creator or a manager with `role`.
+    pub(crate) fn assert_trial_role(&self, trial_id: TrialId, role: TrialRole) -> TrialData {
+        assert_not_self_call();
+
+        let trial_data = self
+            .internal_get_trial(trial_id)
+            .unwrap_or_else(|| TrialError::TrialNotFound { trial_id }.panic());
+
+        if !trial_data.has_role(&env::predecessor_account_id(), role) {
+            TrialError::MissingTrialRole { role }.panic();
+        }
+
+        trial_data
+    }
+
+    /// Emits `ActionSigned` for a payload the current trial key signs for an action,
+    /// then asks the MPC contract to sign it.
+    pub(crate) fn internal_sign_payload(
+        &self,
+        trial_id: TrialId,
+        action: &Action,
+        hashed_payload: [u8; 32],
+    ) -> Promise {
+        emit_action_signed(trial_id, action, &[hashed_payload]);
+        self.request_mpc_signature(hashed_payload, &env::signer_account_pk())
+    }
+
+    /// Like `internal_sign_payload`, for actions that need several signatures.
+    pub(crate) fn internal_sign_payloads(
+        &self,
+        trial_id: TrialId,
+        action: &Action,
+        hashed_payloads: &[[u8; 32]],
+    ) -> Promise {
+        emit_action_signed(trial_id, action, hashed_payloads);
+        self.request_mpc_signatures(hashed_payloads, &env::signer_account_pk())
+    }
+
+    /// Asks the MPC contract to sign a hashed payload with the key derived from `path`.
+    pub(crate) fn request_mpc_signature(
+        &self,
+        hashed_payload: [u8; 32],
+        path: &PublicKey,
+    ) -> Promise {
+        self.mpc_sign_call(hashed_payload, path).as_return()
+    }
+
+    /// Asks the MPC contract to sign several hashed payloads. A joint promise can't be
+    /// returned, so `on_mpc_signatures` collects the signatures in the order requested.
+    pub(crate) fn request_mpc_signatures(
+        &self,
+        hashed_payloads: &[[u8; 32]],
+        path: &PublicKey,
+    ) -> Promise {
+        hashed_payloads
+            .iter()
+            .map(|hashed_payload| self.mpc_sign_call(*hashed_payload, path))
+            .reduce(Promise::and)
+            .unwrap_or_else(|| TrialError::NoPayloadsToSign.panic())
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MPC_SIGNATURES_CALLBACK_GAS)
+                    .on_mpc_signatures(),
+            )
+    }
+
+    /// Returns the signatures requested by `request_mpc_signatures`, one per payload.
+    #[private]
+    pub fn on_mpc_signatures(&self) -> Vec<SignResult> {
+        (0..env::promise_results_count())
+            .map(|index| match env::promise_result(index) {
+                PromiseResult::Successful(result) => near_sdk::serde_json::from_slice(&result)
+                    .unwrap_or_else(|_| TrialError::MpcSignatureFailed { index }.panic()),
+                _ => TrialError::MpcSignatureFailed { index }.panic(),
+            })
+            .collect()
+    }
+
+    fn mpc_sign_call(&self, hashed_payload: [u8; 32], path: &PublicKey) -> Promise {
+        let request_payload = create_sign_request_from_transaction(hashed_payload, path);
+
+        // Call the MPC contract to get a signature
+        Promise::new(self.mpc_contract.clone()).function_call_weight(
+            "sign".to_string(),
+            near_sdk::serde_json::to_vec(&request_payload).unwrap(),
+            NearToken::from_near(1),
+            Gas::from_tgas(30),
+            GasWeight(1),
+        )
+    }
+
+    /// Adds a trial to its creator's index of trials.
+    pub(crate) fn internal_add_trial_to_creator(&mut self, creator: &AccountId, trial_id: TrialId) {
+        if !self.trials_by_creator.contains_key(creator) {
+            let storage_key = StorageKeys::TrialsByCreatorInner {
+                account_id_hash: env::sha256_array(creator.as_str().as_bytes()),
+            };
+            self.trials_by_creator.insert(creator.clone(), IterableSet::new(storage_key));
+        }
+
+        let trials = self.trials_by_creator.get_mut(creator).unwrap();
+        trials.insert(trial_id);
+        trials.flush();
+        self.trials_by_creator.flush();
+    }
+
+    /// Removes a trial from its creator's index, dropping the index once it is empty.
+    pub(crate) fn internal_remove_trial_from_creator(
+        &mut self,
+        creator: &AccountId,
+        trial_id: TrialId,
+    ) {
+        if let Some(trials) = self.trials_by_creator.get_mut(creator) {
+            trials.remove(&trial_id);
+            trials.flush();
+            if trials.is_empty() {
+                self.trials_by_creator.remove(creator);
+            }
+        }
+        self.trials_by_creator.flush();
+    }
+
+    /// Adds public keys to the index of keys issued for a trial.
+    pub(crate) fn internal_add_keys_to_trial(
+        &mut self,
+        trial_id: TrialId,
+        public_keys: &[PublicKey],
+    ) {
+        if !self.keys_by_trial.contains_key(&trial_id) {
+            let storage_key = StorageKeys::KeysByTrialInner { trial_id };
+            self.keys_by_trial.insert(trial_id, IterableSet::new(storage_key));
+        }
+
+        let keys = self.keys_by_trial.get_mut(&trial_id).unwrap();
+        for public_key in public_keys {
+            keys.insert(public_key.clone());
+        }
+        keys.flush();
+        self.keys_by_trial.flush();
+    }
+
+    /// Removes keys from the index of keys issued for a trial.
+    pub(crate) fn internal_remove_keys_from_trial(
+        &mut self,
+        trial_id: TrialId,
+        public_keys: &[PublicKey],
+    ) {
+        if let Some(keys) = self.keys_by_trial.get_mut(&trial_id) {
+            for public_key in public_keys {
+                keys.remove(public_key);
+            }
+            keys.flush();
+        }
+        self.keys_by_trial.flush();
+    }
+
+    /// Removes up to `limit` of a trial's keys, along with their usage and access keys.
+    /// Returns the removed keys and whether the trial has no keys left.
+    pub(crate) fn internal_remove_trial_keys_batch(
+        &mut self,
+        trial_id: TrialId,
+        limit: usize,
+    ) -> (Vec<PublicKey>, bool) {
+        let Some(keys) = self.keys_by_trial.get_mut(&trial_id) else {
+            return (Vec::new(), true);
+        };
+
+        let public_keys: Vec<PublicKey> = keys.iter().take(limit).cloned().collect();
+        for public_key in public_keys.iter() {
+            keys.remove(public_key);
+        }
+        keys.flush();
+        let is_empty = keys.is_empty();
+
+        for public_key in public_keys.iter() {
+            self.internal_remove_key_usage(public_key);
+            Promise::new(env::current_account_id()).delete_key(public_key.clone());
+        }
+        if is_empty {
+            self.keys_by_trial.remove(&trial_id);
+        }
+        self.keys_by_trial.flush();
+        self.key_usage_by_pk.flush();
+
+        (public_keys, is_empty)
+    }
+
+    /// Charges storage growth caused by trial keys to the trial's balance,
+    /// or credits the balance back when storage is freed.
+    pub(crate) fn charge_trial_storage(
+        &mut self,
+        trial_id: TrialId,
+        initial_storage: u64,
+        final_storage: u64,
+    ) {
+        if final_storage > initial_storage {
+            let required = storage_cost(final_storage - initial_storage);
+            self.internal_debit_trial_balance(trial_id, required);
+        } else {
+            let refund = storage_cost(initial_storage - final_storage);
+            let trial_data = self
+                .internal_get_trial_mut(trial_id)
+                .unwrap_or_else(|| TrialError::TrialNotFound { trial_id }.panic());
+            trial_data.balance = trial_data.balance.saturating_add(refund);
+        }
+    }
+
+    /// Takes an amount out of a trial's balance.
+    pub(crate) fn internal_debit_trial_balance(&mut self, trial_id: TrialId, amount: NearToken) {
+        let trial_data = self
+            .internal_get_trial_mut(trial_id)
+            .unwrap_or_else(|| TrialError::TrialNotFound { trial_id }.panic());
+
+        let available = trial_data.balance;
+        trial_data.balance = available.checked_sub(amount).unwrap_or_else(|| {
+            TrialError::InsufficientTrialBalance {
+                required: amount.as_yoctonear(),
+                available: available.as_yoctonear(),
+            }
+            .panic()
+        });
+    }
+
     pub(crate) fn adjust_deposit(&self, initial_storage: u64, final_storage: u64) {
+        self.adjust_deposit_with_fee(initial_storage, final_storage, NearToken::from_yoctonear(0));
+    }
+
+    /// Same as `adjust_deposit`, but also keeps `fee` out of the attached deposit.
+    pub(crate) fn adjust_deposit_with_fee(
+        &self,
+        initial_storage: u64,
+        final_storage: u64,
+        fee: NearToken,
+    ) {
+        if env::attached_deposit() < fee {
+            TrialError::InsufficientDeposit {
+                required: fee.as_yoctonear(),
+            }
+            .panic();
+        }
+
         // Measure the storage difference
         let storage_used = final_storage as i64 - initial_storage as i64;
         let storage_byte_cost = env::storage_byte_cost();
@@ -136,14 +666,16 @@ impl Contract {
         if storage_used > 0 {
             let required_deposit = storage_byte_cost
                 .checked_mul(storage_used as u128)
-                .expect("Overflow");
+                .and_then(|storage_deposit| storage_deposit.checked_add(fee))
+                .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
             let attached_deposit = env::attached_deposit();
 
-            assert!(
-                attached_deposit >= required_deposit,
-                "Insufficient deposit: required {} yoctoNEAR",
-                required_deposit
-            );
+            if attached_deposit < required_deposit {
+                TrialError::InsufficientDeposit {
+                    required: required_deposit.as_yoctonear(),
+                }
+                .panic();
+            }
 
             // Refund any excess deposit
             let refund = attached_deposit.checked_sub(required_deposit);
@@ -155,7 +687,7 @@ impl Contract {
         else if storage_used < 0 {
             let refund_amount = storage_byte_cost
                 .checked_mul((-storage_used) as u128)
-                .expect("Overflow");
+                .unwrap_or_else(|| TrialError::ArithmeticOverflow.panic());
 
             // Transfer the refund for freed storage
             Promise::new(env::predecessor_account_id()).transfer(refund_amount);
@@ -1,4 +1,8 @@
 use crate::*;
+use near_sdk::json_types::U128;
+
+/// Default page size for paginated views.
+const DEFAULT_PAGE_LIMIT: u64 = 50;
 
 #[derive(Clone)]
 #[near(serializers = [json, borsh])]
@@ -9,6 +13,10 @@ pub struct ExtTrialData {
     pub exit_conditions: Option<ExitConditions>,
     pub expiration_time: Option<u64>,
     pub creator_account_id: AccountId,
+    pub balance: NearToken,
+    pub paused: bool,
+    pub managers: HashMap<AccountId, Vec<TrialRole>>,
+    pub key_allowance: Option<NearToken>,
 }
 
 /// Associates a public key with its usage stats and trial ID.
@@ -30,60 +38,128 @@ pub struct ExtKeyUsage {
     pub mpc_key: PublicKey,
     pub account_id_by_chain_id: HashMap<ChainId, String>,
     pub usage_stats: UsageStats,
+    pub frozen: bool,
+    pub exited: bool,
+    pub allowance: NearToken,
+    pub last_nonce_by_chain_id: HashMap<ChainId, u64>,
 }
 
 #[near]
 impl Contract {
     /// View function to get trial data by trial ID
     pub fn get_trial_data(&self, trial_id: TrialId) -> Option<TrialData> {
-        self.trial_data_by_id.get(&trial_id).cloned()
+        self.internal_get_trial(trial_id)
+    }
+
+    /// View function to get the aggregated usage of a trial
+    pub fn get_trial_stats(&self, trial_id: TrialId) -> Option<ExtTrialStats> {
+        self.trial_stats_by_id
+            .get(&trial_id)
+            .cloned()
+            .map(ExtTrialStats::from)
     }
 
     /// View function to get key usage by public key
     pub fn get_key_usage(&self, public_key: PublicKey) -> Option<ExtKeyUsage> {
-        self.key_usage_by_pk
-            .get(&public_key)
-            .cloned()
-            .map(|key_usage| {
-                let mut account_id_by_chain_id = HashMap::new();
-                for (chain_id, account_id) in key_usage.account_id_by_chain_id.iter() {
-                    match account_id {
-                        UserAccountId::EVM(addr) => {
-                            account_id_by_chain_id
-                                .insert(chain_id.clone(), convert_address_to_hex_string(addr));
-                        }
-                        UserAccountId::NEAR(acc_id) => {
-                            account_id_by_chain_id.insert(chain_id.clone(), acc_id.to_string());
-                        }
-                    }
-                }
+        self.internal_get_key_usage(&public_key).map(|key_usage| {
+            let account_id_by_chain_id = key_usage
+                .account_id_by_chain_id
+                .iter()
+                .map(|(chain_id, account_id)| (chain_id.clone(), account_id.to_string()))
+                .collect();
 
-                ExtKeyUsage {
-                    trial_id: key_usage.trial_id,
-                    mpc_key: key_usage.mpc_key,
-                    account_id_by_chain_id,
-                    usage_stats: key_usage.usage_stats,
-                }
+            ExtKeyUsage {
+                trial_id: key_usage.trial_id,
+                mpc_key: key_usage.mpc_key,
+                account_id_by_chain_id,
+                usage_stats: key_usage.usage_stats,
+                frozen: key_usage.frozen,
+                exited: key_usage.exited,
+                allowance: key_usage.allowance,
+                last_nonce_by_chain_id: key_usage.last_nonce_by_chain_id,
+            }
+        })
+    }
+
+    /// Paginated view of the trial IDs created by an account.
+    pub fn get_trials_by_creator(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<TrialId> {
+        self.trials_by_creator
+            .get(&account_id)
+            .map(|trials| {
+                trials
+                    .iter()
+                    .skip(from_index.map_or(0, |index| index.0 as usize))
+                    .take(limit.unwrap_or(DEFAULT_PAGE_LIMIT) as usize)
+                    .copied()
+                    .collect()
             })
+            .unwrap_or_default()
+    }
+
+    /// Paginated view of the public keys issued for a trial.
+    pub fn get_keys_for_trial(
+        &self,
+        trial_id: TrialId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<PublicKey> {
+        self.keys_by_trial
+            .get(&trial_id)
+            .map(|keys| {
+                keys.iter()
+                    .skip(from_index.map_or(0, |index| index.0 as usize))
+                    .take(limit.unwrap_or(DEFAULT_PAGE_LIMIT) as usize)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn get_chain_metadata(&self, chain_id: ChainId) -> Option<ChainMetadata> {
+        self.chain_registry.get(&chain_id).cloned()
+    }
+
+    /// Paginated view of the chain registry.
+    pub fn get_chains(
+        &self,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<(ChainId, ChainMetadata)> {
+        self.chain_registry
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0 as usize))
+            .take(limit.unwrap_or(DEFAULT_PAGE_LIMIT) as usize)
+            .map(|(chain_id, metadata)| (chain_id.clone(), metadata.clone()))
+            .collect()
+    }
+
+    /// Total number of trials that currently exist.
+    pub fn get_trial_supply(&self) -> u64 {
+        self.num_trials
+    }
+
+    /// Number of trials that currently exist for a creator.
+    pub fn get_trial_supply_for_creator(&self, account_id: AccountId) -> u64 {
+        self.trials_by_creator
+            .get(&account_id)
+            .map_or(0, |trials| trials.len() as u64)
     }
 
     pub fn get_trial_account_info(&self, public_key: PublicKey) -> Option<TrialAccountInfo> {
-        self.key_usage_by_pk.get(&public_key).and_then(|key_usage| {
+        self.internal_get_key_usage(&public_key).and_then(|key_usage| {
             let trial_id = key_usage.trial_id;
-            self.trial_data_by_id.get(&trial_id).map(|trial_data| {
-                let trial_data_converted = trial_data_to_ext_trial_data(trial_data.clone());
-                let mut account_id_by_chain_id = HashMap::new();
-                for (chain_id, account_id) in key_usage.account_id_by_chain_id.iter() {
-                    match account_id {
-                        UserAccountId::EVM(addr) => {
-                            account_id_by_chain_id
-                                .insert(chain_id.clone(), convert_address_to_hex_string(addr));
-                        }
-                        UserAccountId::NEAR(acc_id) => {
-                            account_id_by_chain_id.insert(chain_id.clone(), acc_id.to_string());
-                        }
-                    }
-                }
+            self.internal_get_trial(trial_id).map(|trial_data| {
+                let trial_data_converted = trial_data_to_ext_trial_data(trial_data);
+                let account_id_by_chain_id = key_usage
+                    .account_id_by_chain_id
+                    .iter()
+                    .map(|(chain_id, account_id)| (chain_id.clone(), account_id.to_string()))
+                    .collect();
 
                 TrialAccountInfo {
                     trial_id,